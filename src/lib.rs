@@ -0,0 +1,5988 @@
+use ncurses::*;
+#[cfg(feature = "chrono")]
+use chrono::{Datelike, Days, Local, Months, NaiveDate};
+#[cfg(feature = "strum")]
+use strum::IntoEnumIterator;
+use std::any::Any;
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::cmp;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+use std::panic;
+use std::ops::{Add, Mul, Range, RangeInclusive, Sub};
+use std::num::IntErrorKind;
+
+#[derive(Default, Copy, Clone, PartialEq, Eq, Debug)]
+pub struct Point(pub i32, pub i32);
+
+impl Add for Point {
+    type Output = Self;
+
+    fn add(self, Self(x, y): Self) -> Self {
+        Self(self.0 + x, self.1 + y)
+    }
+}
+
+impl Sub for Point {
+    type Output = Self;
+
+    fn sub(self, Self(x, y): Self) -> Self {
+        Self(self.0 - x, self.1 - y)
+    }
+}
+
+impl Mul for Point {
+    type Output = Self;
+
+    fn mul(self, Self(x, y): Self) -> Self {
+        Self(self.0 * x, self.1 * y)
+    }
+}
+
+// Logs what happens to the widgets through the `log` crate, under the
+// "imtui" target, if the feature is enabled. Compiles to nothing
+// otherwise, while still counting the arguments as used.
+#[cfg(feature = "log")]
+macro_rules! trace {
+    ($($arg:tt)*) => {
+        log::debug!(target: "imtui", $($arg)*)
+    };
+}
+
+#[cfg(not(feature = "log"))]
+macro_rules! trace {
+    ($($arg:tt)*) => {
+        if false {
+            let _ = format_args!($($arg)*);
+        }
+    };
+}
+
+#[derive(Default, Copy, Clone, PartialEq, Eq, Debug)]
+pub struct Rect {
+    pub pos: Point,
+    pub size: Point,
+}
+
+impl Rect {
+    pub fn new(pos: Point, size: Point) -> Self {
+        Self {pos, size}
+    }
+
+    pub fn intersect(&self, other: Rect) -> Rect {
+        let x0 = cmp::max(self.pos.0, other.pos.0);
+        let y0 = cmp::max(self.pos.1, other.pos.1);
+        let x1 = cmp::min(self.pos.0 + self.size.0, other.pos.0 + other.size.0);
+        let y1 = cmp::min(self.pos.1 + self.size.1, other.pos.1 + other.size.1);
+        Rect::new(Point(x0, y0), Point(cmp::max(x1 - x0, 0), cmp::max(y1 - y0, 0)))
+    }
+
+    pub fn contains(&self, Point(x, y): Point) -> bool {
+        self.pos.0 <= x && x < self.pos.0 + self.size.0 &&
+        self.pos.1 <= y && y < self.pos.1 + self.size.1
+    }
+}
+
+pub enum LayoutType {
+    Horz,
+    Vert,
+}
+
+/// Where the content of a layout goes across the width it was given.
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub enum Align {
+    Start,
+    Center,
+    End,
+}
+
+impl Align {
+    /// How far something `size` wide moves right to be aligned in `width`.
+    pub fn offset(self, width: i32, size: i32) -> i32 {
+        let room = cmp::max(width - size, 0);
+        match self {
+            Align::Start => 0,
+            Align::Center => room / 2,
+            Align::End => room,
+        }
+    }
+}
+
+// How much was drawn and registered at some point of the frame, so what
+// comes after it can be moved, see ImTui::shift_since().
+#[derive(Default, Copy, Clone, Debug)]
+struct Mark {
+    draws: usize,
+    rects: usize,
+    layout_rects: usize,
+    caret: Option<Point>,
+}
+
+struct Layout {
+    typ: LayoutType,
+    pos: Point,
+    size: Point,
+    pad: i32,
+    children: i32,
+    max_size: Option<Point>,
+    // How many children do not fit before the last row (or column) of
+    // `max_size`, which is where the overflow marker goes.
+    hidden: i32,
+    // Where the widgets registered in the layout start in ImTui::rects.
+    first_rect: usize,
+    // Begun with ImTui::begin_layout_at(), out of the layout around it.
+    detached: bool,
+    // The content is aligned in `width` columns with `align`: each child
+    // on its own in LayoutType::Vert, the whole row in LayoutType::Horz.
+    align: Align,
+    width: i32,
+    // Where the child being drawn started, or the layout itself for
+    // LayoutType::Horz outside of a grid.
+    mark: Mark,
+    grid: Option<Grid>,
+}
+
+// The children of a layout begun with ImTui::begin_grid() go into `cols`
+// columns, row by row. A column is as wide as its widest cell and a row as
+// tall as its tallest one.
+struct Grid {
+    cols: usize,
+    widths: Vec<i32>,
+    heights: Vec<i32>,
+    // Where each cell started and the position it was drawn at.
+    cells: Vec<(Mark, Point)>,
+}
+
+impl Grid {
+    // The position of the cell at `col` and `row` from the top left
+    // corner of the grid, going by the sizes of the cells so far.
+    fn offset(&self, col: usize, row: usize, pad: i32) -> Point {
+        let x: i32 = self.widths[..col].iter().map(|width| width + pad).sum();
+        let y: i32 = self.heights[..row].iter().map(|height| height + pad).sum();
+        Point(x, y)
+    }
+}
+
+impl Layout {
+    fn new(typ: LayoutType, pos: Point, pad: i32) -> Self {
+        Self {
+            typ,
+            pos,
+            size: Point(0, 0),
+            pad,
+            children: 0,
+            max_size: None,
+            hidden: 0,
+            first_rect: 0,
+            detached: false,
+            align: Align::Start,
+            width: 0,
+            mark: Mark::default(),
+            grid: None,
+        }
+    }
+
+    fn overflows(&self) -> bool {
+        match (&self.typ, self.max_size) {
+            (LayoutType::Horz, Some(max_size)) => self.size.0 > max_size.0,
+            (LayoutType::Vert, Some(max_size)) => self.size.1 > max_size.1,
+            (_, None) => false,
+        }
+    }
+
+    // The padding goes only between the children, so there is none
+    // before the first one and none after the last one.
+    fn gap(&self) -> i32 {
+        if self.children > 0 {self.pad} else {0}
+    }
+
+    fn free_pos(&self) -> Point {
+        if let Some(grid) = &self.grid {
+            let cell = self.children as usize;
+            return self.pos + grid.offset(cell % grid.cols, cell / grid.cols, self.pad);
+        }
+        match self.typ {
+            LayoutType::Horz => self.pos + Point(self.size.0 + self.gap(), 0),
+            LayoutType::Vert => self.pos + Point(0, self.size.1 + self.gap()),
+        }
+    }
+
+    fn add_size(&mut self, size: Point) {
+        if let Some(grid) = &mut self.grid {
+            let cell = self.children as usize;
+            let (col, row) = (cell % grid.cols, cell / grid.cols);
+            if col == grid.widths.len() {
+                grid.widths.push(0);
+            }
+            if row == grid.heights.len() {
+                grid.heights.push(0);
+            }
+            grid.widths[col] = cmp::max(grid.widths[col], size.0);
+            grid.heights[row] = cmp::max(grid.heights[row], size.1);
+            self.size = grid.offset(grid.widths.len(), grid.heights.len(), self.pad) - Point(self.pad, self.pad);
+            self.children += 1;
+            return;
+        }
+
+        let gap = self.gap();
+
+        if let Some(max_size) = self.max_size {
+            let end = match self.typ {
+                LayoutType::Horz => (self.size.0 + gap + size.0, max_size.0),
+                LayoutType::Vert => (self.size.1 + gap + size.1, max_size.1),
+            };
+            if end.0 > end.1 - 1 {
+                self.hidden += 1;
+            }
+        }
+
+        match self.typ {
+            LayoutType::Horz => {
+                self.size.0 += gap + size.0;
+                self.size.1 = cmp::max(self.size.1, size.1);
+            },
+            LayoutType::Vert => {
+                self.size.0 = cmp::max(self.size.0, size.0);
+                self.size.1 += gap + size.1;
+            }
+        }
+        self.children += 1;
+    }
+}
+
+#[derive(PartialEq, Eq, Hash, Copy, Clone, Debug)]
+pub struct Id(i32);
+
+impl Id {
+    /// Id of the index-th child of this one, the same every frame, for
+    /// widgets made in a loop. GenId hands out non-negative ids, so the
+    /// derived ones are kept negative to never collide with them.
+    pub fn child(self, index: usize) -> Id {
+        use std::hash::{Hash, Hasher};
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        (self.0, index).hash(&mut hasher);
+        Id(hasher.finish() as i32 | i32::MIN)
+    }
+}
+
+// Id derived from a label, the same every frame without keeping it
+// anywhere, for the widgets whose labels are unique already. Two widgets
+// with the same label get the same id, which register_id() panics on in
+// debug builds, so give those explicit ids instead or tell them apart
+// with ImTui::push_id(). Negative like the child ids.
+impl From<&str> for Id {
+    fn from(label: &str) -> Id {
+        use std::hash::{Hash, Hasher};
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        label.hash(&mut hasher);
+        Id(hasher.finish() as i32 | i32::MIN)
+    }
+}
+
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub enum Key {
+    Char(char),
+    Ctrl(char),
+    Enter,
+    Esc,
+    Tab,
+    BackTab,
+    Backspace,
+    Delete,
+    Up,
+    Down,
+    ShiftUp,
+    ShiftDown,
+    Left,
+    Right,
+    Home,
+    End,
+    PageUp,
+    PageDown,
+    F(u8),
+    /// Text pasted into the terminal, see ImTui::pasted().
+    Paste,
+    /// Anything ncurses reported that is not decoded above.
+    Other(i32),
+}
+
+// The key codes NcursesBackend asks ncurses to report the markers of a
+// bracketed paste with, around the pasted text. Above the ones of ncurses.
+const KEY_PASTE_BEGIN: i32 = KEY_MAX + 1;
+const KEY_PASTE_END: i32 = KEY_MAX + 2;
+
+// Not wrapped by the ncurses crate.
+extern "C" {
+    fn define_key(definition: *const std::os::raw::c_char, keycode: std::os::raw::c_int) -> std::os::raw::c_int;
+}
+
+impl Key {
+    // Decodes what getch() returned in the keypad mode.
+    fn from_code(code: i32) -> Self {
+        match code {
+            10 | 13 | KEY_ENTER => Key::Enter,
+            27 => Key::Esc,
+            9 => Key::Tab,
+            KEY_BTAB => Key::BackTab,
+            8 | 127 | KEY_BACKSPACE => Key::Backspace,
+            KEY_DC => Key::Delete,
+            KEY_UP => Key::Up,
+            KEY_DOWN => Key::Down,
+            // What terminfo calls scrolling forward and backward is sent
+            // by Shift+Down and Shift+Up.
+            KEY_SR => Key::ShiftUp,
+            KEY_SF => Key::ShiftDown,
+            KEY_LEFT => Key::Left,
+            KEY_RIGHT => Key::Right,
+            KEY_HOME => Key::Home,
+            KEY_END => Key::End,
+            KEY_PPAGE => Key::PageUp,
+            KEY_NPAGE => Key::PageDown,
+            // KEY_F() only takes n < 16, the constant is computed by hand.
+            _ if (KEY_F0 + 1..=KEY_F0 + 63).contains(&code) => Key::F((code - KEY_F0) as u8),
+            1..=26 => Key::Ctrl((b'a' + code as u8 - 1) as char),
+            32..=126 => Key::Char(code as u8 as char),
+            _ => Key::Other(code),
+        }
+    }
+}
+
+impl std::fmt::Display for Key {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Key::Char(' ') => write!(f, "Space"),
+            Key::Char(x) => write!(f, "{}", x),
+            Key::Ctrl(x) => write!(f, "Ctrl+{}", x.to_ascii_uppercase()),
+            Key::BackTab => write!(f, "Shift+Tab"),
+            Key::ShiftUp => write!(f, "Shift+Up"),
+            Key::ShiftDown => write!(f, "Shift+Down"),
+            Key::PageUp => write!(f, "PgUp"),
+            Key::PageDown => write!(f, "PgDn"),
+            Key::F(n) => write!(f, "F{}", n),
+            Key::Other(code) => write!(f, "<{}>", code),
+            _ => write!(f, "{:?}", self),
+        }
+    }
+}
+
+/// What the widgets report to happen during a frame, for apps that want
+/// to apply all the changes in one place after ImTui::end() instead of
+/// reacting to the return values of the widgets.
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub enum Action {
+    Activated(Id),
+    Toggled(Id, bool),
+    Changed(Id),
+    /// An item asked to be moved from one index to the other.
+    Moved(Id, usize, usize),
+}
+
+/// The pairs of calls that have to match within a frame.
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub enum Scope {
+    Layout,
+    Clip,
+    Layer,
+    Disabled,
+    Id,
+    Frame,
+    Split,
+}
+
+impl Scope {
+    fn calls(self) -> (&'static str, &'static str) {
+        match self {
+            Scope::Layout => ("begin_layout()", "end_layout()"),
+            Scope::Clip => ("push_clip()", "pop_clip()"),
+            Scope::Layer => ("push_layer()", "pop_layer()"),
+            Scope::Disabled => ("begin_disabled()", "end_disabled()"),
+            Scope::Id => ("push_id()", "pop_id()"),
+            Scope::Frame => ("begin_frame()", "end_frame()"),
+            Scope::Split => ("begin_split()", "end_split()"),
+        }
+    }
+}
+
+/// What end() found wrong with how the frame was built.
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub enum ImTuiError {
+    /// A begin without its end, or the other way around. Only the first
+    /// scope found so in the frame is reported.
+    Unbalanced(Scope),
+}
+
+impl std::fmt::Display for ImTuiError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ImTuiError::Unbalanced(scope) => {
+                let (begin, end) = scope.calls();
+                write!(f, "{} and {} do not match", begin, end)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ImTuiError {}
+
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub enum MouseKind {
+    Press,
+    Release,
+    Move,
+    WheelUp,
+    WheelDown,
+}
+
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub struct MouseEvent {
+    pub pos: Point,
+    pub kind: MouseKind,
+}
+
+#[derive(Default)]
+pub struct ImTui {
+    active: Option<Id>,
+    hot: Option<Id>,
+    hovered: Option<Id>,
+    text_input: Option<Id>,
+    layouts: Vec<Layout>,
+    key: Option<Key>,
+    mouse: Option<MouseEvent>,
+    mouse_pos: Option<Point>,
+    ids: Vec<Id>,
+    // The ids of the last frame in the focus order, see begin_root().
+    prev_ids: Vec<Id>,
+    // The tab indices declared during the frame, see set_tab_index().
+    tab_indices: HashMap<Id, i32>,
+    // Screen area of the registered widgets in the order they were drawn.
+    rects: Vec<(Id, Rect)>,
+    prev_rects: Vec<(Id, Rect)>,
+    // The widget registered last, where it started and at which depth
+    // of the layout stack, until its size is known.
+    pending_rect: Option<(Id, Point, usize)>,
+    // Index of the focused widget in `ids` and its id. The id is what
+    // is kept across frames, the index is recomputed from it.
+    focus: i32,
+    focused: Option<Id>,
+    // The ids of the last frame the focused widget was drawn in, to find
+    // its neighbours once it's gone.
+    focus_ids: Vec<Id>,
+    // Where the focused widget was drawn in this frame, before clipping.
+    focus_rect: Option<Rect>,
+    // The first scope ended in the frame without having begun, e.g.
+    // end_layout() with only the root layout left.
+    unbalanced: Option<Scope>,
+    // The scopes of push_id(), each already combined with the ones around.
+    id_stack: Vec<Id>,
+    // The bytes of a bracketed paste so far, and the last one completed.
+    paste: Option<Vec<u8>>,
+    pasted: String,
+    viewport: Rect,
+    clips: Vec<Rect>,
+    splits: Vec<Split>,
+    // Where each open frame started, its style and title in
+    // `frame_titles`.
+    frames: Vec<(Point, FrameStyle, Range<usize>)>,
+    frame_titles: String,
+    actions: Vec<Action>,
+    // Bound key, the id it fires, what it does for the help_overlay() and
+    // the last frame the widget of the binding was drawn in, None for the
+    // ones of bind() that stay until they are replaced.
+    bindings: Vec<(Key, Id, String, Option<u64>)>,
+    // The id whose binding was pressed this frame.
+    fired: Option<Id>,
+    // The state the widgets keep between frames for themselves, e.g. a
+    // scroll offset, so the caller does not have to own it, along with the
+    // last frame its widget was drawn in. How long an entry stays once
+    // the widget is not drawn any more is up to the StateEviction.
+    states: HashMap<Id, (u64, Box<dyn Any>)>,
+    state_eviction: StateEviction,
+    frame: u64,
+    caret: Option<Point>,
+    // Where end() decided the hardware cursor goes, if it is shown.
+    cursor: Option<Point>,
+    draws: Vec<Draw>,
+    prev_draws: Vec<Draw>,
+    // The text of all the draws back to back, so drawing a frame does not
+    // allocate a string per put_str() once the buffers have grown.
+    draw_text: String,
+    prev_draw_text: String,
+    // What present() put on the screen last, and where.
+    prev_cells: Vec<Vec<Cell>>,
+    prev_viewport: Rect,
+    // The rows present() fills for the new frame and the text of a run of
+    // its cells, kept so presenting a frame does not allocate.
+    cells: Vec<Vec<Cell>>,
+    run_text: String,
+    damage: Vec<Rect>,
+    layers: Vec<i32>,
+    // One per begin_disabled() scope, whether it disables its widgets.
+    disabled: Vec<bool>,
+    // Every id registered in the frame, disabled or not.
+    frame_ids: HashSet<Id>,
+    up_down_ids: HashSet<Id>,
+    // How far the frame being built went past the viewport so far.
+    frame_overflow: Point,
+    overflow: Point,
+    preedit: String,
+    // The bytes of a character getch() returned only some of so far.
+    utf8: Vec<u8>,
+    cursor_visible: bool,
+    feedback: Feedback,
+    scroll_step: i32,
+    poll_timeout: i32,
+    // How many of the base colors the terminal has, up to 16.
+    colors: i16,
+    glyphs: Glyphs,
+    focus_ring: bool,
+    // Outlines the layouts. The rects are collected during the frame and
+    // drawn in end().
+    debug_layout: bool,
+    layout_rects: Vec<Rect>,
+    // When the current frame began.
+    now: Option<Instant>,
+    focus_wrap: bool,
+    focus_wrapped: bool,
+    // The hovered widget and since when, for the tooltips.
+    hover_start: Option<(Id, Instant)>,
+    tooltip_delay: Duration,
+    hover_focus: bool,
+    // Whether a modal was drawn during the frame, see declare_modal().
+    modal: bool,
+    busy: bool,
+    dim_when_busy: bool,
+    theme: Theme,
+    // None for a headless ImTui, which does not draw anywhere.
+    backend: Option<Box<dyn Backend>>,
+}
+
+/// Where present() puts the frames. Nothing else in ImTui talks to the
+/// terminal, and the widgets do not at all: they put_str() into the frame.
+/// The key codes fed to ImTui::feed_key() are the ones of ncurses though.
+pub trait Backend {
+    /// The size of the screen, looked up by every begin().
+    fn size(&self) -> Point;
+    fn move_to(&mut self, pos: Point);
+    /// Sets the colors and the attributes of the put_str() that follow.
+    fn set_pair(&mut self, pair: i16, attr: attr_t);
+    /// Draws `text` from the position moved to.
+    fn put_str(&mut self, text: &str);
+    /// Blanks `width` cells from the position moved to.
+    fn clear(&mut self, width: i32);
+    /// Shows the hardware cursor at the position moved to, or hides it.
+    fn show_cursor(&mut self, visible: bool);
+    /// Puts everything drawn since the last refresh() on the screen.
+    fn refresh(&mut self);
+    fn apply_theme(&mut self, theme: &Theme);
+    /// How many of the base colors there are, up to 16.
+    fn colors(&self) -> i16;
+    fn feedback(&mut self, feedback: Feedback);
+    /// See ImTui::set_poll_timeout().
+    fn set_timeout(&mut self, ms: i32);
+    /// Drops the input waiting to be read.
+    fn flush_input(&mut self);
+    /// The mouse event behind a KEY_MOUSE key code.
+    fn mouse_event(&mut self) -> Option<MouseEvent>;
+}
+
+// Calls endwin() when dropped so the terminal is restored no matter how
+// the application exits.
+struct NcursesBackend;
+
+impl NcursesBackend {
+    // Initializes ncurses, see ImTuiBuilder::build().
+    fn init(builder: &ImTuiBuilder) -> Self {
+        setlocale(LcCategory::all, "");
+        initscr();
+        noecho();
+        timeout(builder.timeout);
+        // Without the keypad mode the arrow and function keys arrive as
+        // raw escape sequences. The delay is how long ncurses waits after
+        // a lone Esc for the rest of a sequence.
+        keypad(stdscr(), true);
+        set_escdelay(25);
+        // In the bracketed paste mode the terminal sends `ESC [ 200 ~`
+        // before the pasted text and `ESC [ 201 ~` after it. ncurses turns
+        // them into single key codes, instead of an Esc with some
+        // characters after it, and ImTui::feed_key() collects what comes
+        // in between.
+        for (definition, code) in [("\x1b[200~", KEY_PASTE_BEGIN), ("\x1b[201~", KEY_PASTE_END)] {
+            let definition = std::ffi::CString::new(definition).unwrap();
+            // SAFETY: ncurses is initialized and copies the definition.
+            unsafe {
+                define_key(definition.as_ptr(), code);
+            }
+        }
+        print!("\x1b[?2004h");
+        io::stdout().flush().ok();
+        // Otherwise the terminal turns Ctrl+C into SIGINT and swallows
+        // Ctrl+V instead of passing them to the edit fields.
+        #[cfg(feature = "clipboard")]
+        raw();
+
+        start_color();
+        builder.theme.apply();
+        init_swatch_pairs(cmp::min(COLORS(), 16) as i16);
+
+        if builder.mouse {
+            // Presses and releases are needed separately for dragging, so
+            // ncurses must not merge them into clicks.
+            mousemask((ALL_MOUSE_EVENTS | REPORT_MOUSE_POSITION) as mmask_t, None);
+            mouseinterval(0);
+        }
+
+        let default_hook = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            endwin();
+            default_hook(info);
+        }));
+
+        NcursesBackend
+    }
+}
+
+impl Backend for NcursesBackend {
+    fn size(&self) -> Point {
+        let mut size = Point(0, 0);
+        getmaxyx(stdscr(), &mut size.1, &mut size.0);
+        size
+    }
+
+    fn move_to(&mut self, pos: Point) {
+        mv(pos.1, pos.0);
+    }
+
+    fn set_pair(&mut self, pair: i16, attr: attr_t) {
+        attrset(COLOR_PAIR(pair) | attr);
+    }
+
+    fn put_str(&mut self, text: &str) {
+        addstr(text);
+    }
+
+    fn clear(&mut self, width: i32) {
+        attrset(0);
+        hline(' ' as chtype, width);
+    }
+
+    fn show_cursor(&mut self, visible: bool) {
+        if visible {
+            curs_set(CURSOR_VISIBILITY::CURSOR_VISIBLE);
+        } else {
+            curs_set(CURSOR_VISIBILITY::CURSOR_INVISIBLE);
+        }
+    }
+
+    fn refresh(&mut self) {
+        refresh();
+    }
+
+    fn apply_theme(&mut self, theme: &Theme) {
+        theme.apply();
+    }
+
+    fn colors(&self) -> i16 {
+        cmp::min(COLORS(), 16) as i16
+    }
+
+    fn feedback(&mut self, feedback: Feedback) {
+        if feedback.audible {
+            beep();
+        }
+        if feedback.visual {
+            flash();
+        }
+    }
+
+    fn set_timeout(&mut self, ms: i32) {
+        timeout(ms);
+    }
+
+    fn flush_input(&mut self) {
+        flushinp();
+    }
+
+    fn mouse_event(&mut self) -> Option<MouseEvent> {
+        let mut event = MEVENT {id: 0, x: 0, y: 0, z: 0, bstate: 0};
+        if getmouse(&mut event) != OK {
+            return None;
+        }
+        let has = |mask: i32| event.bstate & mask as mmask_t != 0;
+        let kind = if has(BUTTON1_PRESSED) {
+            MouseKind::Press
+        } else if has(BUTTON1_RELEASED) {
+            MouseKind::Release
+        } else if has(BUTTON4_PRESSED) {
+            MouseKind::WheelUp
+        } else if has(BUTTON5_PRESSED) {
+            MouseKind::WheelDown
+        } else if has(REPORT_MOUSE_POSITION) {
+            MouseKind::Move
+        } else {
+            return None;
+        };
+        Some(MouseEvent {pos: Point(event.x, event.y), kind})
+    }
+}
+
+impl Drop for NcursesBackend {
+    fn drop(&mut self) {
+        endwin();
+        print!("\x1b[?2004l");
+        io::stdout().flush().ok();
+    }
+}
+
+/// A screen of `size` cells in memory, for tests driving the whole loop
+/// and checking what got drawn, with no terminal. The clones share the
+/// screen, so a test keeps one and gives another to
+/// ImTuiBuilder::build_with_backend().
+#[derive(Clone)]
+pub struct TestBackend {
+    screen: Rc<RefCell<TestScreen>>,
+}
+
+struct TestScreen {
+    // The character and the pair of each cell.
+    cells: Vec<Vec<(char, i16)>>,
+    pos: Point,
+    pair: i16,
+    cursor: Option<Point>,
+    feedbacks: usize,
+}
+
+impl TestBackend {
+    pub fn new(size: Point) -> Self {
+        let cells = vec![vec![(' ', INACTIVE_PAIR); cmp::max(size.0, 0) as usize]; cmp::max(size.1, 0) as usize];
+        let screen = TestScreen {cells, pos: Point(0, 0), pair: INACTIVE_PAIR, cursor: None, feedbacks: 0};
+        Self {screen: Rc::new(RefCell::new(screen))}
+    }
+
+    /// A space outside of the screen.
+    pub fn cell(&self, x: i32, y: i32) -> char {
+        self.cell_with_pair(x, y).0
+    }
+
+    /// The pair the cell was drawn with, INACTIVE_PAIR where nothing was.
+    pub fn pair(&self, x: i32, y: i32) -> i16 {
+        self.cell_with_pair(x, y).1
+    }
+
+    fn cell_with_pair(&self, x: i32, y: i32) -> (char, i16) {
+        let screen = self.screen.borrow();
+        screen.cells.get(y as usize).and_then(|row| row.get(x as usize)).cloned().unwrap_or((' ', INACTIVE_PAIR))
+    }
+
+    pub fn row_string(&self, y: i32) -> String {
+        self.screen.borrow().cells.get(y as usize)
+            .map(|row| row.iter().map(|(x, _)| *x).filter(|x| *x != WIDE_TAIL).collect())
+            .unwrap_or_default()
+    }
+
+    /// Where the hardware cursor is shown, if it is.
+    pub fn cursor(&self) -> Option<Point> {
+        self.screen.borrow().cursor
+    }
+
+    /// How many times ImTui::reject() gave the feedback so far.
+    pub fn feedbacks(&self) -> usize {
+        self.screen.borrow().feedbacks
+    }
+}
+
+impl TestScreen {
+    fn put(&mut self, x: char, pair: i16) {
+        let width = char_width(x);
+        for y in [x, WIDE_TAIL].iter().take(width as usize) {
+            let Point(col, row) = self.pos;
+            if let Some(cell) = self.cells.get_mut(row as usize).and_then(|row| row.get_mut(col as usize)) {
+                *cell = (*y, pair);
+            }
+            self.pos.0 += 1;
+        }
+    }
+}
+
+impl Backend for TestBackend {
+    fn size(&self) -> Point {
+        let screen = self.screen.borrow();
+        Point(screen.cells.first().map(|row| row.len()).unwrap_or(0) as i32, screen.cells.len() as i32)
+    }
+
+    fn move_to(&mut self, pos: Point) {
+        self.screen.borrow_mut().pos = pos;
+    }
+
+    fn set_pair(&mut self, pair: i16, _attr: attr_t) {
+        self.screen.borrow_mut().pair = pair;
+    }
+
+    fn put_str(&mut self, text: &str) {
+        let mut screen = self.screen.borrow_mut();
+        let pair = screen.pair;
+        for x in text.chars() {
+            screen.put(x, pair);
+        }
+    }
+
+    fn clear(&mut self, width: i32) {
+        let mut screen = self.screen.borrow_mut();
+        let pos = screen.pos;
+        for _ in 0..width {
+            screen.put(' ', INACTIVE_PAIR);
+        }
+        screen.pos = pos;
+    }
+
+    fn show_cursor(&mut self, visible: bool) {
+        let mut screen = self.screen.borrow_mut();
+        screen.cursor = if visible {Some(screen.pos)} else {None};
+    }
+
+    fn refresh(&mut self) {}
+
+    fn apply_theme(&mut self, _theme: &Theme) {}
+
+    fn colors(&self) -> i16 {
+        16
+    }
+
+    fn feedback(&mut self, _feedback: Feedback) {
+        self.screen.borrow_mut().feedbacks += 1;
+    }
+
+    fn set_timeout(&mut self, _ms: i32) {}
+
+    fn flush_input(&mut self) {}
+
+    fn mouse_event(&mut self) -> Option<MouseEvent> {
+        None
+    }
+}
+
+/// Draws with ANSI escapes into any writer instead of the terminal of
+/// ncurses, e.g. a pipe, a pty or the connection of an SSH session. The
+/// output is collected until refresh() and written out in one go, an
+/// error of the writer drops the frame. There is no input: the keys are
+/// fed to ImTui::feed_key() by the caller.
+pub struct AnsiBackend<W: Write> {
+    writer: W,
+    size: Point,
+    theme: Theme,
+    out: String,
+}
+
+impl<W: Write> AnsiBackend<W> {
+    /// A screen of `size` cells in the colors of the default theme, until
+    /// ImTui::set_theme() applies another.
+    pub fn new(writer: W, size: Point) -> Self {
+        Self {writer, size, theme: Theme::default(), out: String::new()}
+    }
+
+    /// The terminal on the other end was resized.
+    pub fn set_size(&mut self, size: Point) {
+        self.size = size;
+    }
+
+    pub fn writer(&self) -> &W {
+        &self.writer
+    }
+
+    pub fn into_writer(self) -> W {
+        self.writer
+    }
+}
+
+impl<W: Write> Backend for AnsiBackend<W> {
+    fn size(&self) -> Point {
+        self.size
+    }
+
+    fn move_to(&mut self, pos: Point) {
+        self.out.push_str(&format!("\x1b[{};{}H", pos.1 + 1, pos.0 + 1));
+    }
+
+    fn set_pair(&mut self, pair: i16, attr: attr_t) {
+        let (fg, bg) = self.theme.colors(pair);
+        self.out.push_str(&format!("\x1b[0;38;5;{};48;5;{}", fg, bg));
+        for (flag, code) in [(A_BOLD(), 1), (A_DIM(), 2), (A_UNDERLINE(), 4), (A_REVERSE(), 7)] {
+            if attr & flag != 0 {
+                self.out.push_str(&format!(";{}", code));
+            }
+        }
+        self.out.push('m');
+    }
+
+    fn put_str(&mut self, text: &str) {
+        self.out.push_str(text);
+    }
+
+    fn clear(&mut self, width: i32) {
+        // Erases the cells without moving, like hline() of ncurses.
+        self.out.push_str(&format!("\x1b[0m\x1b[{}X", width));
+    }
+
+    fn show_cursor(&mut self, visible: bool) {
+        self.out.push_str(if visible {"\x1b[?25h"} else {"\x1b[?25l"});
+    }
+
+    fn refresh(&mut self) {
+        let out = std::mem::take(&mut self.out);
+        self.writer.write_all(out.as_bytes()).and_then(|_| self.writer.flush()).ok();
+    }
+
+    fn apply_theme(&mut self, theme: &Theme) {
+        self.theme = theme.clone();
+    }
+
+    fn colors(&self) -> i16 {
+        16
+    }
+
+    // There is no flash in ANSI, only the bell.
+    fn feedback(&mut self, feedback: Feedback) {
+        if feedback.audible {
+            self.out.push('\x07');
+        }
+    }
+
+    fn set_timeout(&mut self, _ms: i32) {}
+
+    fn flush_input(&mut self) {}
+
+    fn mouse_event(&mut self) -> Option<MouseEvent> {
+        None
+    }
+}
+
+// A cell of the screen: the character drawn there with its pair and
+// attributes, if anything was.
+type Cell = Option<(char, i16, attr_t)>;
+
+// The `viewport` after `draws`, already sorted by layer, as rows of
+// cells. The second half of a wide character whose first half is out of
+// the viewport is a space.
+fn cell_grid(draws: &[Draw], text: &str, viewport: Rect) -> Vec<Vec<Cell>> {
+    let mut cells = Vec::new();
+    fill_cell_grid(&mut cells, draws, text, viewport);
+    cells
+}
+
+// cell_grid() into `cells`, reusing the rows it has.
+fn fill_cell_grid(cells: &mut Vec<Vec<Cell>>, draws: &[Draw], text: &str, viewport: Rect) {
+    let Rect {pos, size} = viewport;
+    let inside = |cell: Point| Rect::new(Point(0, 0), size).contains(cell);
+    cells.resize_with(cmp::max(size.1, 0) as usize, Vec::new);
+    for row in cells.iter_mut() {
+        row.clear();
+        row.resize(cmp::max(size.0, 0) as usize, None);
+    }
+    for draw in draws {
+        let mut column = 0;
+        for x in text[draw.text.clone()].chars() {
+            let width = char_width(x);
+            let head = draw.pos + Point(column, 0) - pos;
+            for (i, y) in [x, WIDE_TAIL].iter().take(width as usize).enumerate() {
+                let cell = head + Point(i as i32, 0);
+                if inside(cell) {
+                    let y = if i > 0 && !inside(head) {' '} else {*y};
+                    cells[cell.1 as usize][cell.0 as usize] = Some((y, draw.pair, draw.attr));
+                }
+            }
+            column += width;
+        }
+    }
+}
+
+// cell_grid() with the character and the pair of each cell, a space in
+// INACTIVE_PAIR where nothing was drawn.
+fn cells(draws: &[Draw], text: &str, viewport: Rect) -> Vec<Vec<(char, i16)>> {
+    cell_grid(draws, text, viewport).into_iter()
+        .map(|row| row.into_iter().map(|cell| cell.map_or((' ', INACTIVE_PAIR), |(x, pair, _)| (x, pair))).collect())
+        .collect()
+}
+
+// Puts the cells of `new` that are not the same in `old` on the screen
+// at `pos`, clearing the ones nothing is drawn in any more, and adds
+// where it did to `damage`. The neighbouring changed cells of a row in
+// the same pair go in one put_str(), their text put together in `text`.
+fn flush_cells(backend: &mut Option<Box<dyn Backend>>, damage: &mut Vec<Rect>, text: &mut String, pos: Point, old: &[Vec<Cell>], new: &[Vec<Cell>]) {
+    let style = |cell: Cell| cell.map(|(_, pair, attr)| (pair, attr));
+    for (y, row) in new.iter().enumerate() {
+        let differs = |x: usize| old.get(y).and_then(|old| old.get(x)) != Some(&row[x]);
+        // A wide character is drawn from its first half.
+        let changed = |x: usize| differs(x) ||
+            (x + 1 < row.len() && differs(x + 1) && matches!(row[x + 1], Some((WIDE_TAIL, _, _))));
+
+        let mut x = 0;
+        while x < row.len() {
+            if !changed(x) {
+                x += 1;
+                continue;
+            }
+            let end = (x..row.len())
+                .find(|end| !changed(*end) || style(row[*end]) != style(row[x]))
+                .unwrap_or(row.len());
+            let start = pos + Point(x as i32, y as i32);
+            let width = (end - x) as i32;
+            if let Some(backend) = backend.as_mut() {
+                backend.move_to(start);
+                match style(row[x]) {
+                    Some((pair, attr)) => {
+                        text.clear();
+                        text.extend(row[x..end].iter().flatten()
+                            .map(|(x, _, _)| *x)
+                            .filter(|x| *x != WIDE_TAIL));
+                        backend.set_pair(pair, attr);
+                        backend.put_str(text);
+                    }
+                    None => backend.clear(width),
+                }
+            }
+            damage.push(Rect::new(start, Point(width, 1)));
+            x = end;
+        }
+    }
+}
+
+// What is in the second cell of a wide character, for cells() and
+// TestBackend. Left out when they are turned into text.
+const WIDE_TAIL: char = '\0';
+
+// Above everything the app draws.
+const DEBUG_LAYER: i32 = i32::MAX;
+const TOOLTIP_LAYER: i32 = DEBUG_LAYER - 1;
+
+// A put_str() waiting for present(). The text is a range of the frame's
+// draw_text.
+#[derive(PartialEq, Eq, Clone, Debug)]
+struct Draw {
+    pos: Point,
+    text: Range<usize>,
+    pair: i16,
+    attr: attr_t,
+    layer: i32,
+}
+
+/// The decorations of the checkbox, radio and button widgets.
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub struct Glyphs {
+    pub checked: &'static str,
+    pub unchecked: &'static str,
+    pub selected: &'static str,
+    pub unselected: &'static str,
+    pub button: (&'static str, &'static str),
+}
+
+impl Default for Glyphs {
+    fn default() -> Self {
+        Self::ascii()
+    }
+}
+
+impl Glyphs {
+    /// Works everywhere.
+    pub fn ascii() -> Self {
+        Self {
+            checked: "[X]",
+            unchecked: "[ ]",
+            selected: "(*)",
+            unselected: "( )",
+            button: ("[ ", " ]"),
+        }
+    }
+
+    /// Takes less room, for the terminals with a font having these.
+    pub fn unicode() -> Self {
+        Self {
+            checked: "☑",
+            unchecked: "☐",
+            selected: "◉",
+            unselected: "○",
+            button: ("‹ ", " ›"),
+        }
+    }
+
+    /// The characters of the widgets, to be drawn with put_chars() without
+    /// building a string of them every frame.
+    pub fn checkbox<'a>(&self, state: bool, text: &'a str) -> impl Iterator<Item = char> + 'a {
+        let glyph = if state {self.checked} else {self.unchecked};
+        glyph.chars().chain(std::iter::once(' ')).chain(text.chars())
+    }
+
+    pub fn radio<'a>(&self, selected: bool, text: &'a str) -> impl Iterator<Item = char> + 'a {
+        let glyph = if selected {self.selected} else {self.unselected};
+        glyph.chars().chain(std::iter::once(' ')).chain(text.chars())
+    }
+
+    pub fn button<'a>(&self, label: &'a str) -> impl Iterator<Item = char> + 'a {
+        self.button.0.chars().chain(label.chars()).chain(self.button.1.chars())
+    }
+}
+
+/// How the user is told that an input was rejected, e.g. moving past the
+/// end of a list. Both are off by default.
+#[derive(Default, PartialEq, Eq, Copy, Clone, Debug)]
+pub struct Feedback {
+    pub audible: bool,
+    pub visual: bool,
+}
+
+/// When the state ImTui keeps for a widget is dropped after the widget
+/// stops being drawn. Never keeps it around for when the widget comes
+/// back, e.g. in a collapsed section, but grows with every id ever seen.
+#[derive(Default, PartialEq, Eq, Copy, Clone, Debug)]
+pub enum StateEviction {
+    #[default]
+    Never,
+    /// At the end of the first frame without the widget.
+    Immediate,
+    /// After that many frames without the widget.
+    AfterFrames(u32),
+}
+
+pub struct ImTuiBuilder {
+    timeout: i32,
+    theme: Theme,
+    mouse: bool,
+    cursor_visible: bool,
+    feedback: Feedback,
+    scroll_step: i32,
+    state_eviction: StateEviction,
+    glyphs: Glyphs,
+    focus_ring: bool,
+    dim_when_busy: bool,
+    tooltip_delay: Duration,
+    hover_focus: bool,
+    focus_wrap: bool,
+}
+
+impl Default for ImTuiBuilder {
+    fn default() -> Self {
+        Self {
+            timeout: 16,
+            theme: Theme::default(),
+            mouse: false,
+            cursor_visible: false,
+            feedback: Feedback::default(),
+            scroll_step: 3,
+            state_eviction: StateEviction::Never,
+            glyphs: Glyphs::ascii(),
+            focus_ring: false,
+            dim_when_busy: true,
+            tooltip_delay: Duration::from_millis(500),
+            hover_focus: false,
+            focus_wrap: true,
+        }
+    }
+}
+
+impl ImTuiBuilder {
+    /// See ImTui::set_poll_timeout().
+    pub fn with_timeout(mut self, timeout: i32) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    pub fn with_theme(mut self, theme: Theme) -> Self {
+        self.theme = theme;
+        self
+    }
+
+    pub fn with_mouse(mut self, mouse: bool) -> Self {
+        self.mouse = mouse;
+        self
+    }
+
+    /// Keep the hardware cursor visible, parked in the bottom right corner
+    /// of the viewport, even when no text input is active.
+    pub fn with_cursor_visible(mut self, cursor_visible: bool) -> Self {
+        self.cursor_visible = cursor_visible;
+        self
+    }
+
+    pub fn with_feedback(mut self, feedback: Feedback) -> Self {
+        self.feedback = feedback;
+        self
+    }
+
+    /// How many lines a notch of the mouse wheel scrolls.
+    pub fn with_scroll_step(mut self, scroll_step: i32) -> Self {
+        self.scroll_step = scroll_step;
+        self
+    }
+
+    pub fn with_glyphs(mut self, glyphs: Glyphs) -> Self {
+        self.glyphs = glyphs;
+        self
+    }
+
+    /// See ImTui::set_focus_ring().
+    pub fn with_focus_ring(mut self, focus_ring: bool) -> Self {
+        self.focus_ring = focus_ring;
+        self
+    }
+
+    /// See ImTui::set_busy().
+    pub fn with_dim_when_busy(mut self, dim_when_busy: bool) -> Self {
+        self.dim_when_busy = dim_when_busy;
+        self
+    }
+
+    /// How long the mouse has to rest on a widget for its tooltip() to
+    /// show up.
+    pub fn with_tooltip_delay(mut self, tooltip_delay: Duration) -> Self {
+        self.tooltip_delay = tooltip_delay;
+        self
+    }
+
+    /// Moving the mouse over a widget focuses it, instead of only drawing
+    /// it as hovered. See ImTui::begin_root() for how it goes along with
+    /// the keyboard.
+    pub fn with_hover_focus(mut self, hover_focus: bool) -> Self {
+        self.hover_focus = hover_focus;
+        self
+    }
+
+    /// Whether Tab and the arrows move the focus from the last widget on
+    /// to the first one and back, on by default. Off, the focus stops at
+    /// the ends.
+    pub fn with_focus_wrap(mut self, focus_wrap: bool) -> Self {
+        self.focus_wrap = focus_wrap;
+        self
+    }
+
+    pub fn with_state_eviction(mut self, state_eviction: StateEviction) -> Self {
+        self.state_eviction = state_eviction;
+        self
+    }
+
+    /// Initializes ncurses. The terminal is restored when the returned
+    /// ImTui is dropped, and also before a panic message is printed so it
+    /// does not get lost in the alternate screen.
+    pub fn build(self) -> ImTui {
+        let backend = NcursesBackend::init(&self);
+        self.build_with_backend(Box::new(backend))
+    }
+
+    /// Draws the frames with `backend` instead of ncurses. It is set up
+    /// already, with the theme applied.
+    pub fn build_with_backend(self, backend: Box<dyn Backend>) -> ImTui {
+        ImTui {
+            colors: backend.colors(),
+            viewport: Rect::new(Point(0, 0), backend.size()),
+            backend: Some(backend),
+            ..self.build_headless(Point(0, 0))
+        }
+    }
+
+    /// Like build() but without the terminal, for driving whole frames
+    /// from tests or CI without a TTY: nothing is drawn and the keys are
+    /// fed by the caller. screen() and frame_text() tell what present()
+    /// would have put on a `size` screen.
+    pub fn build_headless(self, size: Point) -> ImTui {
+        ImTui {
+            viewport: Rect::new(Point(0, 0), size),
+            cursor_visible: self.cursor_visible,
+            feedback: self.feedback,
+            scroll_step: self.scroll_step,
+            poll_timeout: self.timeout,
+            colors: 16,
+            state_eviction: self.state_eviction,
+            glyphs: self.glyphs,
+            focus_ring: self.focus_ring,
+            dim_when_busy: self.dim_when_busy,
+            tooltip_delay: self.tooltip_delay,
+            hover_focus: self.hover_focus,
+            focus_wrap: self.focus_wrap,
+            theme: self.theme,
+            ..ImTui::default()
+        }
+    }
+}
+
+impl ImTui {
+    pub fn init() -> ImTuiBuilder {
+        ImTuiBuilder::default()
+    }
+
+    /// Headless the viewport stays the size given to build_headless().
+    pub fn begin(&mut self, pos: Point) {
+        if let Some(backend) = &self.backend {
+            self.viewport = Rect::new(Point(0, 0), backend.size());
+        }
+        self.begin_root(pos);
+    }
+
+    /// Whether the viewport is at least `min` in size. If it is not, says
+    /// so in the middle of it instead, and the app is supposed to skip
+    /// drawing the rest of the frame, which would not fit anyway.
+    pub fn require_min_size(&mut self, min: Point) -> bool {
+        let Rect {pos, size} = self.viewport;
+        if size.0 >= min.0 && size.1 >= min.1 {
+            return true;
+        }
+
+        let lines = [
+            "Terminal too small".to_string(),
+            format!("{}x{}, needs {}x{}", size.0, size.1, min.0, min.1),
+        ];
+        for (i, line) in lines.iter().enumerate() {
+            let x = cmp::max((size.0 - text_width(line)) / 2, 0);
+            let y = cmp::max(size.1 / 2 - 1, 0) + i as i32;
+            self.put_str(pos + Point(x, y), line, INACTIVE_PAIR);
+        }
+
+        false
+    }
+
+    /// Like begin() but all the drawing of the frame is offset to
+    /// `viewport.pos` and clipped to `viewport.size`, so the UI can share
+    /// the screen with other ncurses content.
+    pub fn begin_viewport(&mut self, viewport: Rect) {
+        self.viewport = viewport;
+        self.begin_root(viewport.pos);
+    }
+
+    fn begin_root(&mut self, pos: Point) {
+        self.now = Some(Instant::now());
+
+        // Esc always gets the user out of the active widget, whichever
+        // it is, so the widgets do not handle it themselves. Unless a
+        // modal is open: then Esc is left to it to close.
+        let modal = std::mem::take(&mut self.modal);
+        if self.key == Some(Key::Esc) && !modal {
+            if let Some(id) = self.active.take() {
+                trace!("frame {}: {:?} is cancelled", self.frame, id);
+                self.key = None;
+            }
+        }
+
+        // The bindings go before anything else, a bound key is taken away
+        // from the focus navigation and the widgets. The only exception is
+        // an active widget, e.g. a text input, which gets every key.
+        self.fired = None;
+        if !self.any_active() {
+            if let Some(key) = self.key {
+                if let Some((_, id, _, _)) = self.bindings.iter().rev().find(|(x, _, _, _)| *x == key) {
+                    self.fired = Some(*id);
+                    self.key = None;
+                }
+            }
+        }
+
+        // The focus is resolved and moved among the ids the last frame
+        // registered, all at once before the widgets of this one register
+        // theirs, so `focus` is always an index into that one list. The
+        // widgets see the result when they are drawn. If the focused one
+        // is not drawn any more, no widget is hot for that frame, and the
+        // next begin() hands the focus to a neighbour, see resolve_focus().
+        // Navigation keys are only handled when nothing is active. In
+        // particular an active text input receives every key, so typing
+        // 'w' or 's' into it never moves the focus.
+        // The focus order is the draw order unless the tab indices say
+        // otherwise. The sort is stable so the ties keep the draw order.
+        std::mem::swap(&mut self.ids, &mut self.prev_ids);
+        self.ids.clear();
+        let tab_indices = &self.tab_indices;
+        self.prev_ids.sort_by_key(|id| tab_indices.get(id).cloned().unwrap_or(0));
+        self.tab_indices.clear();
+
+        self.resolve_focus();
+
+        // Up and Down move the focus too, unless the focused widget uses
+        // them itself, see claim_up_down(). They are consumed then, so the
+        // newly focused widget does not act on them as well.
+        self.focus_wrapped = false;
+        if self.active.is_none() && !self.prev_ids.is_empty() {
+            let n = self.prev_ids.len() as i32;
+            let arrows = !self.focused.is_some_and(|id| self.up_down_ids.contains(&id));
+            match self.key {
+                Some(Key::Char('s')) | Some(Key::Tab) => self.step_focus(1, n),
+                Some(Key::Char('w')) | Some(Key::BackTab) => self.step_focus(-1, n),
+                Some(Key::Down) if arrows => {
+                    self.step_focus(1, n);
+                    self.key = None;
+                }
+                Some(Key::Up) if arrows => {
+                    self.step_focus(-1, n);
+                    self.key = None;
+                }
+                // Home and End jump to the ends, there is nothing to wrap.
+                Some(Key::Home) => self.focus = 0,
+                Some(Key::End) => self.focus = n - 1,
+                _ => {},
+            }
+            self.focused = Some(self.prev_ids[self.focus as usize]);
+        }
+        self.up_down_ids.clear();
+
+        // The widget under the mouse is looked up in the previous frame,
+        // since this one has not been laid out yet.
+        std::mem::swap(&mut self.rects, &mut self.prev_rects);
+        self.rects.clear();
+        self.hovered = self.mouse_pos.and_then(|pos| self.widget_at(pos));
+
+        // With the hover focus a move of the mouse focuses the widget it
+        // moved onto, the same as the navigation keys above do, so the
+        // last of the two to move wins: a key moves the focus on from the
+        // hovered widget, and the mouse takes it back only once it moves
+        // again. Nothing changes while a widget is active.
+        let moved = matches!(self.mouse, Some(MouseEvent {kind: MouseKind::Move, ..}));
+        if self.hover_focus && moved && self.active.is_none() {
+            if let Some(i) = self.prev_ids.iter().position(|x| Some(*x) == self.hovered) {
+                self.focus = i as i32;
+                self.focused = Some(self.prev_ids[i]);
+            }
+        }
+
+        if self.hot != self.focused {
+            trace!("frame {}: {:?} is hot", self.frame, self.focused);
+        }
+        self.hot = self.focused;
+        self.hover_start = match (self.hovered, self.hover_start) {
+            (Some(id), Some((start_id, since))) if start_id == id => Some((id, since)),
+            (Some(id), _) => Some((id, self.now())),
+            (None, _) => None,
+        };
+
+        self.layouts.push(Layout::new(LayoutType::Vert, pos, 0));
+        self.frame_ids.clear();
+        self.focus_rect = None;
+        self.actions.clear();
+    }
+
+    // Moves the focus `by` widgets of the `n` ones, around the ends unless
+    // the focus wrap is off, see ImTuiBuilder::with_focus_wrap(). Without
+    // it the focus stops at the first and the last widget, and the key
+    // trying to get past them is rejected.
+    fn step_focus(&mut self, by: i32, n: i32) {
+        let next = self.focus + by;
+        if (0..n).contains(&next) {
+            self.focus = next;
+        } else if self.focus_wrap {
+            self.focus_wrapped = true;
+            self.focus = next.rem_euclid(n);
+        } else {
+            self.reject();
+            self.focus = next.clamp(0, n - 1);
+        }
+    }
+
+    // Finds the focused widget among the ids of the previous frame. If it
+    // was not drawn there (e.g. its section got collapsed) the focus moves
+    // to the nearest widget around it that still is, preferring the ones
+    // after it.
+    fn resolve_focus(&mut self) {
+        if self.prev_ids.is_empty() {
+            self.focus = 0;
+            self.focused = None;
+            return;
+        }
+
+        let position = |ids: &[Id], id: Id| ids.iter().position(|x| *x == id);
+
+        if let Some(id) = self.focused {
+            if let Some(i) = position(&self.prev_ids, id) {
+                self.focus = i as i32;
+                if self.focus_ids != self.prev_ids {
+                    self.focus_ids.clone_from(&self.prev_ids);
+                }
+                return;
+            }
+
+            if let Some(i) = position(&self.focus_ids, id) {
+                let n = self.focus_ids.len();
+                for d in 1..n {
+                    let around = [i.checked_add(d).filter(|j| *j < n), i.checked_sub(d)];
+                    for j in around.iter().flatten() {
+                        if let Some(k) = position(&self.prev_ids, self.focus_ids[*j]) {
+                            self.focus = k as i32;
+                            self.focused = Some(self.prev_ids[k]);
+                            self.focus_ids.clone_from(&self.prev_ids);
+                            return;
+                        }
+                    }
+                }
+            }
+        }
+
+        self.focus = self.focus.clamp(0, self.prev_ids.len() as i32 - 1);
+        self.focused = Some(self.prev_ids[self.focus as usize]);
+        self.focus_ids.clone_from(&self.prev_ids);
+    }
+
+    // The topmost widget of the previous frame containing `pos`.
+    fn widget_at(&self, pos: Point) -> Option<Id> {
+        self.prev_rects.iter().rev()
+            .find(|(_, rect)| rect.contains(pos))
+            .map(|(id, _)| *id)
+    }
+
+    pub fn begin_layout(&mut self, typ: LayoutType, pad: i32) {
+        self.begin_layout_aligned(typ, pad, Align::Start, 0);
+    }
+
+    /// Like begin_layout() but the layout is at least `width` wide and its
+    /// content is aligned in it with `align`. The widgets draw where they
+    /// would anyway and are moved when their size is known, by then they
+    /// are already clipped to where they were drawn at first.
+    pub fn begin_layout_aligned(&mut self, typ: LayoutType, pad: i32, align: Align, width: i32) {
+        let pos = self.layouts.last().unwrap().free_pos();
+        let mut layout = Layout::new(typ, pos, pad);
+        layout.align = align;
+        layout.width = width;
+        layout.mark = self.mark();
+        self.layouts.push(layout);
+    }
+
+    fn mark(&self) -> Mark {
+        Mark {
+            draws: self.draws.len(),
+            rects: self.rects.len(),
+            layout_rects: self.layout_rects.len(),
+            caret: self.caret,
+        }
+    }
+
+    // Moves everything drawn and registered between `from` and `to` by
+    // `offset`.
+    fn shift(&mut self, from: Mark, to: Mark, offset: Point) {
+        if offset == Point(0, 0) {
+            return;
+        }
+        for draw in &mut self.draws[from.draws..to.draws] {
+            draw.pos = draw.pos + offset;
+        }
+        for (_, rect) in &mut self.rects[from.rects..to.rects] {
+            rect.pos = rect.pos + offset;
+        }
+        for rect in &mut self.layout_rects[from.layout_rects..to.layout_rects] {
+            rect.pos = rect.pos + offset;
+        }
+        if from.caret != to.caret && self.caret == to.caret {
+            self.caret = self.caret.map(|caret| caret + offset);
+        }
+    }
+
+    // Adds a child of `size` to the current layout, first aligning it if
+    // the layout aligns each child on its own.
+    fn advance_layout(&mut self, size: Point) {
+        let mark = self.mark();
+        let layout = self.layouts.last_mut().unwrap();
+        let from = layout.mark;
+        let pos = layout.free_pos();
+        if let Some(grid) = &mut layout.grid {
+            grid.cells.push((from, pos));
+        }
+        let offset = match layout.typ {
+            LayoutType::Vert => layout.align.offset(layout.width, size.0),
+            LayoutType::Horz => 0,
+        };
+        layout.add_size(size);
+        if let (LayoutType::Vert, _) | (_, Some(_)) = (&layout.typ, &layout.grid) {
+            layout.mark = mark;
+        }
+        self.shift(from, mark, Point(offset, 0));
+    }
+
+    /// Lays out the children in `cols` columns until end_layout(), a row
+    /// after another, with `pad` empty cells between the columns and the
+    /// rows. free_pos() is the top left corner of the next cell, which
+    /// can only go by the cells drawn so far: the widths of the columns are
+    /// only final with the last row, so end_layout() moves the cells of
+    /// the columns that turned out wider into place.
+    pub fn begin_grid(&mut self, cols: usize, pad: i32) {
+        let pos = self.layouts.last().unwrap().free_pos();
+        let mut layout = Layout::new(LayoutType::Horz, pos, pad);
+        layout.mark = self.mark();
+        layout.grid = Some(Grid {
+            cols: cmp::max(cols, 1),
+            widths: Vec::new(),
+            heights: Vec::new(),
+            cells: Vec::new(),
+        });
+        self.layouts.push(layout);
+    }
+
+    fn end_grid_cells(&mut self, layout: &Layout) {
+        if let Some(grid) = &layout.grid {
+            for (i, &(from, pos)) in grid.cells.iter().enumerate() {
+                let to = grid.cells.get(i + 1).map(|(mark, _)| *mark).unwrap_or(layout.mark);
+                let offset = layout.pos + grid.offset(i % grid.cols, i / grid.cols, layout.pad) - pos;
+                self.shift(from, to, offset);
+            }
+        }
+    }
+
+    /// Like begin_layout() but the children beyond `max_size` are clipped.
+    /// If there are any, the last row (or column for LayoutType::Horz) of
+    /// the layout is replaced with a marker telling how many of them are
+    /// not shown.
+    pub fn begin_layout_max(&mut self, typ: LayoutType, pad: i32, max_size: Point) {
+        let pos = self.layouts.last().unwrap().free_pos();
+        let mut layout = Layout::new(typ, pos, pad);
+        layout.max_size = Some(max_size);
+        layout.first_rect = self.rects.len();
+        self.layouts.push(layout);
+        self.push_clip(Rect::new(pos, max_size));
+    }
+
+    pub fn end_layout(&mut self) {
+        // The root layout is for end() to end, the detached ones for
+        // end_layout_at().
+        if self.layouts.len() <= 1 || self.layouts.last().is_some_and(|layout| layout.detached) {
+            self.report_unbalanced(Scope::Layout);
+            return;
+        }
+        let mut layout = self.layouts.pop().unwrap();
+        if let Some(max_size) = layout.max_size {
+            self.pop_clip();
+
+            if layout.overflows() {
+                // The widgets under the marker, or past it, are not there
+                // for the focus and the mouse either.
+                let marker_line = layout.pos + max_size - Point(1, 1);
+                let mut i = layout.first_rect;
+                while i < self.rects.len() {
+                    let (id, Rect {pos, size}) = self.rects[i];
+                    let hidden = match layout.typ {
+                        LayoutType::Horz => pos.0 + size.0 > marker_line.0,
+                        LayoutType::Vert => pos.1 + size.1 > marker_line.1,
+                    };
+                    if hidden {
+                        self.rects.remove(i);
+                        self.ids.retain(|x| *x != id);
+                    } else {
+                        i += 1;
+                    }
+                }
+
+                match layout.typ {
+                    LayoutType::Horz => {
+                        let pos = layout.pos + Point(max_size.0 - 1, 0);
+                        for y in 0..cmp::min(layout.size.1, max_size.1) {
+                            self.put_str(pos + Point(0, y), "▶", INACTIVE_PAIR);
+                        }
+                    }
+                    LayoutType::Vert => {
+                        // The layout is made wide enough for the whole
+                        // marker if it can be.
+                        let marker = format!("▼ (+{} more)", layout.hidden);
+                        let width = cmp::min(cmp::max(layout.size.0, text_width(&marker)), max_size.0);
+                        layout.size.0 = width;
+                        let pos = layout.pos + Point(0, max_size.1 - 1);
+                        self.push_clip(Rect::new(layout.pos, max_size));
+                        self.put_str_fill(pos, &marker, INACTIVE_PAIR, width);
+                        self.pop_clip();
+                    }
+                }
+            }
+
+            layout.size.0 = cmp::min(layout.size.0, max_size.0);
+            layout.size.1 = cmp::min(layout.size.1, max_size.1);
+        }
+        self.end_grid_cells(&layout);
+        if let LayoutType::Horz = layout.typ {
+            let offset = layout.align.offset(layout.width, layout.size.0);
+            let mark = self.mark();
+            self.shift(layout.mark, mark, Point(offset, 0));
+        }
+        layout.size.0 = cmp::max(layout.size.0, layout.width);
+        if self.debug_layout {
+            self.layout_rects.push(Rect::new(layout.pos, layout.size));
+        }
+        self.advance_layout(layout.size);
+    }
+
+    fn report_unbalanced(&mut self, scope: Scope) {
+        self.unbalanced.get_or_insert(scope);
+    }
+
+    /// A layout at `pos` on the screen instead of the free position of the
+    /// current layout, for the containers placing their content themselves,
+    /// e.g. in a pane or inside a border. It takes no room in the current
+    /// layout: end_layout_at() gives the size of the content instead.
+    pub fn begin_layout_at(&mut self, typ: LayoutType, pos: Point, pad: i32) {
+        let mut layout = Layout::new(typ, pos, pad);
+        layout.detached = true;
+        self.layouts.push(layout);
+    }
+
+    /// Ends the layout of the last begin_layout_at(), and the layouts left
+    /// open in it. None if there is no such layout.
+    pub fn end_layout_at(&mut self) -> Option<Point> {
+        let depth = match self.layouts.iter().rposition(|layout| layout.detached) {
+            Some(depth) => depth,
+            None => {
+                self.report_unbalanced(Scope::Layout);
+                return None;
+            }
+        };
+        if self.layouts.len() != depth + 1 {
+            self.report_unbalanced(Scope::Layout);
+        }
+        while self.layouts.len() > depth + 1 {
+            self.end_layout();
+        }
+        self.layouts.pop().map(|layout| layout.size)
+    }
+
+    /// Ends the frame even if the scopes were not balanced, ending the
+    /// layouts left open and dropping the other scopes, so the next frame
+    /// starts clean, but reports it then.
+    pub fn end(&mut self) -> Result<(), ImTuiError> {
+        // A frame or split left open leaves its layout open too, the
+        // former is what was missed. Ending the layouts pops the clips of
+        // begin_layout_max().
+        if !self.frames.is_empty() {
+            self.report_unbalanced(Scope::Frame);
+        }
+        if !self.splits.is_empty() {
+            self.report_unbalanced(Scope::Split);
+        }
+        if self.layouts.len() != 1 {
+            self.report_unbalanced(Scope::Layout);
+        }
+        while self.layouts.len() > 1 {
+            if self.layouts.last().is_some_and(|layout| layout.detached) {
+                self.end_layout_at();
+            } else {
+                self.end_layout();
+            }
+        }
+        let open = [
+            (Scope::Clip, self.clips.is_empty()),
+            (Scope::Layer, self.layers.is_empty()),
+            (Scope::Disabled, self.disabled.is_empty()),
+            (Scope::Id, self.id_stack.is_empty()),
+        ];
+        for (scope, empty) in open {
+            if !empty {
+                self.report_unbalanced(scope);
+            }
+        }
+        self.frames.clear();
+        self.frame_titles.clear();
+        self.splits.clear();
+        self.clips.clear();
+        self.layers.clear();
+        self.disabled.clear();
+        self.id_stack.clear();
+        let root = match self.layouts.pop() {
+            Some(root) => root,
+            None => return Err(ImTuiError::Unbalanced(Scope::Layout)),
+        };
+        self.track_overflow(Rect::new(root.pos, root.size));
+        if self.focus_ring {
+            self.draw_focus_ring();
+        }
+        if self.debug_layout {
+            self.layout_rects.push(Rect::new(root.pos, root.size));
+            self.draw_layout_rects();
+        }
+        if self.busy && self.dim_when_busy {
+            for draw in &mut self.draws {
+                draw.attr |= A_DIM();
+            }
+        }
+        self.overflow = std::mem::take(&mut self.frame_overflow);
+        self.key = None;
+        self.mouse = None;
+        self.evict_states();
+        let frame = self.frame;
+        self.bindings.retain(|(_, _, _, drawn)| drawn.is_none_or(|x| x == frame));
+        self.frame += 1;
+
+        // The hardware cursor is only shown on the caret of the active
+        // text input. Otherwise it is hidden, or parked away from the
+        // widgets if the app asked to keep it visible.
+        let Rect {pos: vpos, size: vsize} = self.viewport;
+        self.cursor = match self.caret.take() {
+            Some(caret) => Some(caret),
+            None if self.cursor_visible => Some(vpos + vsize - Point(1, 1)),
+            None => None,
+        };
+
+        if let Some(scope) = self.unbalanced.take() {
+            let error = ImTuiError::Unbalanced(scope);
+            trace!("frame {}: {}", self.frame - 1, error);
+            return Err(error);
+        }
+        Ok(())
+    }
+
+    /// Puts the frame built between begin() and end() on the screen. This
+    /// is the only place that touches it: the draws are done layer by
+    /// layer, and not at all if they are the same as the last frame's.
+    pub fn present(&mut self) {
+        self.draws.sort_by_key(|draw| draw.layer);
+        let changed = self.draws != self.prev_draws || self.draw_text != self.prev_draw_text ||
+            self.viewport != self.prev_viewport;
+        self.damage.clear();
+        if changed {
+            // Only the cells that changed are drawn, rather than the whole
+            // screen, see damage(). What the last frame drew is cleared
+            // first if the viewport moved, and the new one drawn whole.
+            let mut cells = std::mem::take(&mut self.cells);
+            fill_cell_grid(&mut cells, &self.draws, &self.draw_text, self.viewport);
+            if self.viewport != self.prev_viewport {
+                let blank: Vec<Vec<Cell>> = self.prev_cells.iter().map(|row| vec![None; row.len()]).collect();
+                flush_cells(&mut self.backend, &mut self.damage, &mut self.run_text, self.prev_viewport.pos, &self.prev_cells, &blank);
+                self.prev_cells = cells.iter().map(|row| vec![None; row.len()]).collect();
+                self.prev_viewport = self.viewport;
+            }
+            flush_cells(&mut self.backend, &mut self.damage, &mut self.run_text, self.viewport.pos, &self.prev_cells, &cells);
+            self.cells = std::mem::replace(&mut self.prev_cells, cells);
+        }
+
+        if let Some(backend) = self.backend.as_mut() {
+            if let Some(cursor) = self.cursor {
+                backend.move_to(cursor);
+            }
+            backend.show_cursor(self.cursor.is_some());
+            backend.refresh();
+        }
+
+        std::mem::swap(&mut self.draws, &mut self.prev_draws);
+        std::mem::swap(&mut self.draw_text, &mut self.prev_draw_text);
+        self.draws.clear();
+        self.draw_text.clear();
+    }
+
+    /// What the last present() changed on the screen: nothing if the frame
+    /// was the same as the one before, otherwise one rect per run of the
+    /// changed cells in a row. Everything else on the screen was left as
+    /// it was, for the apps drawing there with ncurses themselves.
+    pub fn damage(&self) -> &[Rect] {
+        &self.damage
+    }
+
+    /// The last frame present() put on the screen, as rows of cells with
+    /// the character and the pair it was drawn with.
+    pub fn screen(&self) -> Vec<Vec<(char, i16)>> {
+        cells(&self.prev_draws, &self.prev_draw_text, self.viewport)
+    }
+
+    /// Writes the last presented frame to `path` as plain text, or with the
+    /// colors of the theme as ANSI escapes if `ansi` (for `cat` or `less -R`).
+    /// Meant for bug reports about how something was drawn.
+    pub fn dump_frame(&self, path: &Path, ansi: bool) -> io::Result<()> {
+        fs::write(path, self.frame_text(ansi))
+    }
+
+    /// dump_frame() into any writer instead of a file, e.g. a pipe, a
+    /// socket or a buffer of a test. Goes well with a headless ImTui, see
+    /// ImTuiBuilder::build_headless().
+    pub fn write_frame(&self, writer: &mut impl io::Write, ansi: bool) -> io::Result<()> {
+        writer.write_all(self.frame_text(ansi).as_bytes())?;
+        writer.flush()
+    }
+
+    /// What dump_frame() writes, one line per row of the screen.
+    pub fn frame_text(&self, ansi: bool) -> String {
+        let mut out = String::new();
+        for row in self.screen() {
+            if ansi {
+                let mut current = None;
+                for (x, pair) in row {
+                    if x == WIDE_TAIL {
+                        continue;
+                    }
+                    if current != Some(pair) {
+                        let (fg, bg) = self.theme.colors(pair);
+                        out.push_str(&format!("\x1b[38;5;{};48;5;{}m", fg, bg));
+                        current = Some(pair);
+                    }
+                    out.push(x);
+                }
+                out.push_str("\x1b[0m");
+            } else {
+                let line: String = row.iter().map(|(x, _)| *x).filter(|x| *x != WIDE_TAIL).collect();
+                out.push_str(line.trim_end());
+            }
+            out.push('\n');
+        }
+        out
+    }
+
+    /// end() and present() for the apps that do nothing in between.
+    pub fn end_and_present(&mut self) -> Result<(), ImTuiError> {
+        let result = self.end();
+        self.present();
+        result
+    }
+
+    // Marks the hot widget with `▸` on its left, or with `◂` on its right
+    // if it touches the left edge of the viewport, for when the colors
+    // alone are not enough to tell it apart.
+    fn draw_focus_ring(&mut self) {
+        let rect = self.hot.and_then(|hot| {
+            self.rects.iter().find(|(id, _)| *id == hot).map(|(_, rect)| *rect)
+        });
+        if let Some(Rect {pos, size}) = rect {
+            if pos.0 > self.viewport.pos.0 {
+                self.put_str(pos - Point(1, 0), "▸", INACTIVE_PAIR);
+            } else {
+                self.put_str(pos + Point(size.0, 0), "◂", INACTIVE_PAIR);
+            }
+        }
+    }
+
+    // Inverts the cells along the edges of every layout of the frame, so
+    // what was drawn there can still be read.
+    fn draw_layout_rects(&mut self) {
+        self.draws.sort_by_key(|draw| draw.layer);
+        let cells = cells(&self.draws, &self.draw_text, self.viewport);
+
+        self.push_layer(DEBUG_LAYER);
+        for i in 0..self.layout_rects.len() {
+            let Rect {pos, size} = self.layout_rects[i];
+            for y in 0..size.1 {
+                for x in 0..size.0 {
+                    if y != 0 && y != size.1 - 1 && x != 0 && x != size.0 - 1 {
+                        continue;
+                    }
+                    let cell = pos + Point(x, y) - self.viewport.pos;
+                    if Rect::new(Point(0, 0), self.viewport.size).contains(cell) {
+                        let (ch, pair) = cells[cell.1 as usize][cell.0 as usize];
+                        self.put_chars(pos + Point(x, y), Some(ch), 1, pair, A_REVERSE());
+                    }
+                }
+            }
+        }
+        self.pop_layer();
+        self.layout_rects.clear();
+    }
+
+    fn evict_states(&mut self) {
+        for id in &self.ids {
+            if let Some((seen, _)) = self.states.get_mut(id) {
+                *seen = self.frame;
+            }
+        }
+
+        let grace = match self.state_eviction {
+            StateEviction::Never => return,
+            StateEviction::Immediate => 0,
+            StateEviction::AfterFrames(frames) => frames as u64,
+        };
+        let frame = self.frame;
+        self.states.retain(|_, (seen, _)| frame - *seen <= grace);
+    }
+
+    /// Draws `text` at `pos` with the color `pair`, clipping everything
+    /// that falls outside of the viewport. Nothing appears on the screen
+    /// until present().
+    pub fn put_str(&mut self, pos: Point, text: &str, pair: i16) {
+        self.put_str_attr(pos, text, pair, A_NORMAL());
+    }
+
+    /// put_str() padded with spaces to `width` columns, so the background
+    /// of `pair` covers the whole row and not only the text.
+    pub fn put_str_fill(&mut self, pos: Point, text: &str, pair: i16, width: i32) {
+        let pad = cmp::max(width - text_width(text), 0) as usize;
+        let chars = text.chars().chain(std::iter::repeat_n(' ', pad));
+        self.put_chars(pos, chars, text_width(text) + pad as i32, pair, 0);
+    }
+
+    /// Paints `rect` with the background of `pair`.
+    pub fn fill(&mut self, rect: Rect, pair: i16) {
+        for y in 0..rect.size.1 {
+            self.put_str_fill(rect.pos + Point(0, y), "", pair, rect.size.0);
+        }
+    }
+
+    /// put_str() with extra attributes like A_UNDERLINE() on top of the
+    /// pair.
+    pub fn put_str_attr(&mut self, pos: Point, text: &str, pair: i16, attr: attr_t) {
+        self.put_chars(pos, text.chars(), text_width(text), pair, attr);
+    }
+
+    /// Draws the `width` cells of `chars` without building a string of them
+    /// first.
+    pub fn put_chars(&mut self, pos: Point, chars: impl IntoIterator<Item = char>, width: i32, pair: i16, attr: attr_t) {
+        self.track_overflow(Rect::new(pos, Point(width, 1)));
+        let attr = if self.is_disabled() {attr | A_DIM()} else {attr};
+
+        let Rect {pos: vpos, size: vsize} = self.clip_rect();
+        if pos.1 < vpos.1 || pos.1 >= vpos.1 + vsize.1 {
+            return;
+        }
+
+        // In columns, the wide characters cut by the clipping region are
+        // left out whole.
+        let skip = cmp::max(vpos.0 - pos.0, 0);
+        let take = cmp::max(vpos.0 + vsize.0 - cmp::max(pos.0, vpos.0), 0);
+        if take == 0 {
+            return;
+        }
+
+        let begin = self.draw_text.len();
+        let mut column = 0;
+        let mut first = None;
+        self.draw_text.extend(chars.into_iter().filter(|x| {
+            let start = column;
+            column += char_width(*x);
+            let shown = start >= skip && column <= skip + take;
+            if shown && first.is_none() {
+                first = Some(start);
+            }
+            shown
+        }));
+        let end = self.draw_text.len();
+        if begin < end {
+            self.draws.push(Draw {
+                pos: Point(pos.0 + first.unwrap_or(skip), pos.1),
+                text: begin..end,
+                pair,
+                attr,
+                layer: self.layer(),
+            });
+        }
+    }
+
+    /// Everything drawn until the matching pop_layer() goes on top of the
+    /// lower layers, no matter in which order it was drawn.
+    pub fn push_layer(&mut self, layer: i32) {
+        self.layers.push(layer);
+    }
+
+    pub fn pop_layer(&mut self) {
+        if self.layers.pop().is_none() {
+            self.report_unbalanced(Scope::Layer);
+        }
+    }
+
+    pub fn layer(&self) -> i32 {
+        self.layers.last().cloned().unwrap_or(0)
+    }
+
+    fn track_overflow(&mut self, rect: Rect) {
+        let far = rect.pos + rect.size - (self.viewport.pos + self.viewport.size);
+        self.frame_overflow.0 = cmp::max(self.frame_overflow.0, far.0);
+        self.frame_overflow.1 = cmp::max(self.frame_overflow.1, far.1);
+    }
+
+    /// Whether the last frame did not fit into the viewport, so the app can
+    /// switch to a more compact layout.
+    pub fn overflowed(&self) -> bool {
+        self.overflow != Point(0, 0)
+    }
+
+    /// By how many columns and rows the last frame went past the right and
+    /// the bottom edges of the viewport.
+    pub fn overflow(&self) -> Point {
+        self.overflow
+    }
+
+    /// The region the drawing is currently clipped to.
+    pub fn clip_rect(&self) -> Rect {
+        self.clips.last().cloned().unwrap_or(self.viewport)
+    }
+
+    pub fn push_clip(&mut self, rect: Rect) {
+        let rect = self.clip_rect().intersect(rect);
+        self.clips.push(rect);
+    }
+
+    /// Until the matching pop_clip() the drawing is clipped to the viewport
+    /// only, for what goes over the containers around, e.g. a popup.
+    pub fn push_clip_viewport(&mut self) {
+        self.clips.push(self.viewport);
+    }
+
+    pub fn pop_clip(&mut self) {
+        if self.clips.pop().is_none() {
+            self.report_unbalanced(Scope::Clip);
+        }
+    }
+
+    pub fn feed_key(&mut self, key: i32) {
+        // What getch() returns once the poll timeout runs out without any
+        // input. The frame is drawn with no key then.
+        if self.busy || key == ERR {
+            return;
+        }
+
+        // All that comes between the markers is the pasted text, the
+        // newlines included. It arrives as Key::Paste once it is complete.
+        if key == KEY_PASTE_BEGIN {
+            self.paste = Some(Vec::new());
+            return;
+        }
+        if let Some(bytes) = &mut self.paste {
+            match key {
+                KEY_PASTE_END => {
+                    let text = String::from_utf8_lossy(bytes).replace("\r\n", "\n").replace('\r', "\n");
+                    self.pasted = text;
+                    self.paste = None;
+                    self.key = Some(Key::Paste);
+                }
+                KEY_ENTER => bytes.push(b'\n'),
+                0..=255 => bytes.push(key as u8),
+                _ => {}
+            }
+            return;
+        }
+
+        if key == KEY_MOUSE {
+            let event = self.backend.as_mut().and_then(|backend| backend.mouse_event());
+            if let Some(MouseEvent {pos, kind}) = event {
+                self.feed_mouse(pos, kind);
+            }
+            return;
+        }
+
+        // Anything but ASCII comes from getch() as its UTF-8 encoding, a
+        // byte at a time. The key is the character once all of them are
+        // there, a byte that does not fit in is dropped.
+        if (0x80..=0xff).contains(&key) {
+            self.utf8.push(key as u8);
+            match std::str::from_utf8(&self.utf8) {
+                Ok(text) => {
+                    self.key = text.chars().next().map(Key::Char);
+                    self.utf8.clear();
+                }
+                Err(err) if err.error_len().is_none() => {}
+                Err(_) => {
+                    // It may start the next character still.
+                    self.utf8.clear();
+                    if key >= 0xc0 {
+                        self.utf8.push(key as u8);
+                    }
+                }
+            }
+            return;
+        }
+        self.utf8.clear();
+
+        self.key = Some(Key::from_code(key))
+    }
+
+    // The primitives the widgets are built from. The widgets of this file
+    // use nothing else, so a widget outside of it can do all they do:
+    //
+    // - register_id() makes the widget focusable this frame, set_rect()
+    //   tells the mouse where it is;
+    // - is_hot()/is_active() with activate()/deactivate() drive the state,
+    //   set_text_input_active() for a widget taking every key;
+    // - key() and mouse() are the input of the frame, wheel() the scrolling
+    //   meant for the widget and hover_time() how long the mouse rests on
+    //   it;
+    // - free_pos() is where to draw, remaining_width() how much room there
+    //   is, put_str() and put_chars() draw, set_caret() places the cursor
+    //   and add_size() advances the layout past the widget;
+    // - take_state() and put_state() keep what the widget needs between
+    //   the frames;
+    // - begin_layout_at(), end_layout_at() and push_clip() are for the
+    //   containers placing their content themselves.
+    //
+    // Only the scopes of begin_disabled(), begin_frame() and begin_split()
+    // are kept in ImTui itself, for end() to check that they are balanced.
+
+    /// Two widgets with the same id in a frame would be focused and
+    /// clicked as one, which is never what was meant, so debug builds
+    /// panic on that.
+    pub fn register_id(&mut self, id: Id) {
+        if !self.frame_ids.insert(id) && cfg!(debug_assertions) {
+            panic!("{:?} is registered twice in frame {}, give each widget its own id", id, self.frame);
+        }
+        if self.is_disabled() {
+            if self.active == Some(id) {
+                self.deactivate();
+            }
+            return;
+        }
+        self.ids.push(id);
+        self.pending_rect = Some((id, self.free_pos(), self.layouts.len()));
+    }
+
+    /// Moves the widget `id` in the focus order: the widgets go by their
+    /// tab index, 0 for the ones without, and then in the order they were
+    /// drawn. So -1 puts a widget before all the others. Like the rest of
+    /// the frame it has to be declared again every frame, and only matters
+    /// while the widget is drawn, a hidden widget is just skipped.
+    pub fn set_tab_index(&mut self, id: Id, index: i32) {
+        self.tab_indices.insert(id, index);
+    }
+
+    /// Whether the focus went around from the last widget to the first one,
+    /// or back, in this frame. E.g. for reject() to let the user know they
+    /// are at the top of a long form again.
+    pub fn focus_wrapped(&self) -> bool {
+        self.focus_wrapped
+    }
+
+    /// Called every frame by the widgets using Up and Down while focused,
+    /// e.g. for moving a highlight, so these keys go to them instead of
+    /// moving the focus. They still get w and s for that.
+    pub fn claim_up_down(&mut self, id: Id) {
+        self.up_down_ids.insert(id);
+    }
+
+    /// Whether the focused widget claimed Up and Down in this frame so far,
+    /// so they are not for the containers around it either.
+    pub fn up_down_claimed(&self) -> bool {
+        self.focused.is_some_and(|x| self.up_down_ids.contains(&x))
+    }
+
+    /// Focuses `id` starting from the widget drawn with it in this frame,
+    /// e.g. the default button of a dialog that was just opened.
+    pub fn set_focus(&mut self, id: Id) {
+        self.focused = Some(id);
+        self.hot = Some(id);
+    }
+
+    /// Overrides the screen area of the widget `id` for the mouse hit
+    /// testing. By default it is where the widget started with the size of
+    /// the first add_size() after register_id() on the same layout.
+    pub fn set_rect(&mut self, id: Id, rect: Rect) {
+        if self.pending_rect.map(|(pending, _, _)| pending) == Some(id) {
+            self.pending_rect = None;
+        }
+        if self.is_disabled() {
+            return;
+        }
+        if self.focused == Some(id) {
+            self.focus_rect = Some(rect);
+        }
+        let rect = self.clip_rect().intersect(rect);
+        self.rects.push((id, rect));
+    }
+
+    /// A disabled widget is never hot, hovered, active nor fired, so it
+    /// does not react to anything and draws itself as inactive.
+    pub fn is_hot(&self, id: Id) -> bool {
+        !self.is_disabled() && self.hot == Some(id)
+    }
+
+    /// Whether the mouse is over the widget. Unlike hot it does not move
+    /// the keyboard focus. When several states apply the widgets are drawn
+    /// with the strongest one: active > hot > hovered > inactive.
+    pub fn is_hovered(&self, id: Id) -> bool {
+        !self.is_disabled() && self.hovered == Some(id)
+    }
+
+    pub fn is_active(&self, id: Id) -> bool {
+        !self.is_disabled() && self.active == Some(id)
+    }
+
+    /// Whether the widgets drawn now are in a begin_disabled() scope that
+    /// disables them.
+    pub fn is_disabled(&self) -> bool {
+        self.disabled.contains(&true)
+    }
+
+    /// Whether some widget is active and is consuming the input.
+    pub fn any_active(&self) -> bool {
+        self.active.is_some()
+    }
+
+    pub fn activate(&mut self, id: Id) {
+        trace!("frame {}: {:?} is active", self.frame, id);
+        self.active = Some(id);
+    }
+
+    pub fn deactivate(&mut self) {
+        self.active = None;
+    }
+
+    /// Called every frame by the widgets that are drawn on top of the rest
+    /// and closed with Esc, which then goes to them instead of cancelling
+    /// the active widget on the next frame.
+    pub fn declare_modal(&mut self) {
+        self.modal = true;
+    }
+
+    /// Called by widgets refusing the current input, gives the configured
+    /// Feedback.
+    pub fn reject(&mut self) {
+        if let Some(backend) = self.backend.as_mut() {
+            backend.feedback(self.feedback);
+        }
+    }
+
+    pub fn key(&self) -> Option<Key> {
+        self.key
+    }
+
+    /// The text of the last Key::Paste.
+    pub fn pasted(&self) -> &str {
+        &self.pasted
+    }
+
+    /// Hides the key of the frame from the widgets drawn after, for a
+    /// widget handling a key before passing the rest on to another one.
+    pub fn consume_key(&mut self) {
+        self.key = None;
+    }
+
+    pub fn mouse(&self) -> Option<MouseEvent> {
+        self.mouse
+    }
+
+    /// Where the mouse was seen last, if it was.
+    pub fn mouse_pos(&self) -> Option<Point> {
+        self.mouse_pos
+    }
+
+    /// How long the mouse has been resting on the widget `id`, None if it
+    /// is not on it. See tooltip_delay().
+    pub fn hover_time(&self, id: Id) -> Option<Duration> {
+        match self.hover_start {
+            Some((hovered, since)) if hovered == id && !self.is_disabled() => {
+                Some(self.now().saturating_duration_since(since))
+            }
+            _ => None,
+        }
+    }
+
+    /// How long the mouse has to rest on a widget for its tooltip, see
+    /// ImTuiBuilder::with_tooltip_delay().
+    pub fn tooltip_delay(&self) -> Duration {
+        self.tooltip_delay
+    }
+
+    /// How many lines the widget `id` should scroll by this frame, negative
+    /// being up. Only the widget under the mouse gets the wheel.
+    pub fn wheel(&self, id: Id) -> i32 {
+        if !self.is_hovered(id) {
+            return 0;
+        }
+        match self.mouse {
+            Some(MouseEvent {kind: MouseKind::WheelUp, ..}) => -self.scroll_step,
+            Some(MouseEvent {kind: MouseKind::WheelDown, ..}) => self.scroll_step,
+            _ => 0,
+        }
+    }
+
+    pub fn free_pos(&self) -> Point {
+        self.layouts.last().unwrap().free_pos()
+    }
+
+    /// How many columns there are from free_pos() to the right edge of the
+    /// current clipping region: the viewport, or the layout or pane it is
+    /// in if that is narrower.
+    pub fn remaining_width(&self) -> i32 {
+        let clip = self.clip_rect();
+        cmp::max(clip.pos.0 + clip.size.0 - self.free_pos().0, 0)
+    }
+
+    pub fn add_size(&mut self, size: Point) {
+        if let Some((id, pos, depth)) = self.pending_rect {
+            if depth == self.layouts.len() {
+                self.set_rect(id, Rect::new(pos, size));
+            }
+        }
+        self.advance_layout(size);
+    }
+
+    /// Takes the state a widget stored for `id` out of ImTui, so the widget
+    /// can work with it while still borrowing ImTui mutably. Put it back
+    /// with put_state() afterwards; a default is left in its place meanwhile.
+    pub fn take_state<T: Default + 'static>(&mut self, id: Id) -> T {
+        self.states.get_mut(&id)
+            .and_then(|(_, state)| state.downcast_mut::<T>())
+            .map(std::mem::take)
+            .unwrap_or_default()
+    }
+
+    /// Reuses the box take_state() left behind, so a widget keeping its
+    /// state across frames does not allocate every frame.
+    pub fn put_state<T: 'static>(&mut self, id: Id, state: T) {
+        let frame = self.frame;
+        match self.states.get_mut(&id) {
+            Some((seen, boxed)) if boxed.is::<T>() => {
+                *seen = frame;
+                *boxed.downcast_mut::<T>().unwrap() = state;
+            }
+            _ => {
+                self.states.insert(id, (frame, Box::new(state)));
+            }
+        }
+    }
+
+    /// The state kept for `id`, made with the default if there is none yet
+    /// or it is of another type. For the state that does not have to be
+    /// around while ImTui is borrowed again, take_state() is for that.
+    pub fn state_mut<T: Default + 'static>(&mut self, id: Id) -> &mut T {
+        let frame = self.frame;
+        let (seen, boxed) = self.states.entry(id).or_insert_with(|| (frame, Box::new(T::default())));
+        *seen = frame;
+        if !boxed.is::<T>() {
+            *boxed = Box::new(T::default());
+        }
+        boxed.downcast_mut::<T>().unwrap()
+    }
+
+    /// Id derived from `key` and the scopes of push_id() around it, so the
+    /// same label makes different ids in different panels or rows of a
+    /// list. Outside of any scope it is the same as `Id::from(label)`.
+    pub fn id(&self, key: impl std::hash::Hash) -> Id {
+        use std::hash::{Hash, Hasher};
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        match self.id_stack.last() {
+            Some(seed) => (seed.0, key).hash(&mut hasher),
+            None => key.hash(&mut hasher),
+        }
+        Id(hasher.finish() as i32 | i32::MIN)
+    }
+
+    /// Until the matching pop_id(), id() makes the ids within the scope of
+    /// `key`, e.g. the index of a row or the name of a panel.
+    pub fn push_id(&mut self, key: impl std::hash::Hash) {
+        let seed = self.id(key);
+        self.id_stack.push(seed);
+    }
+
+    pub fn pop_id(&mut self) {
+        if self.id_stack.pop().is_none() {
+            self.report_unbalanced(Scope::Id);
+        }
+    }
+
+    /// Pressing `key` fires `id`: the button-like widgets with that id
+    /// behave as if they were clicked, and fired() tells about it for the
+    /// ids that belong to no widget.
+    pub fn bind(&mut self, key: Key, id: Id, help: &str) {
+        self.bindings.retain(|(x, _, _, _)| *x != key);
+        self.bindings.push((key, id, help.to_string(), None));
+    }
+
+    /// bind() for a widget drawn in this frame: the binding goes away with
+    /// the first frame the widget is not drawn in. A bind() of the same key
+    /// is left as it is, it is only overridden while the widget is drawn,
+    /// and stays for good when it is of the same id.
+    pub fn bind_widget(&mut self, key: Key, id: Id, help: &str) {
+        let frame = self.frame;
+        match self.bindings.iter_mut().find(|(x, y, _, _)| *x == key && *y == id) {
+            Some((_, _, _, Some(drawn))) => *drawn = frame,
+            Some((_, _, _, None)) => {}
+            None => {
+                self.bindings.retain(|(x, _, _, drawn)| *x != key || drawn.is_none());
+                self.bindings.push((key, id, help.to_string(), Some(frame)));
+            }
+        }
+    }
+
+    pub fn fired(&self, id: Id) -> bool {
+        !self.is_disabled() && self.fired == Some(id)
+    }
+
+    pub fn push_action(&mut self, action: Action) {
+        trace!("frame {}: {:?}", self.frame, action);
+        self.actions.push(action);
+    }
+
+    /// The actions the widgets pushed during the last frame. Should be
+    /// called after end(): the next begin() discards the undrained ones.
+    pub fn drain_actions(&mut self) -> Vec<Action> {
+        std::mem::take(&mut self.actions)
+    }
+
+    pub fn feed_mouse(&mut self, pos: Point, kind: MouseKind) {
+        self.mouse = Some(MouseEvent {pos, kind});
+        self.mouse_pos = Some(pos);
+    }
+
+    /// How long, in milliseconds, getch() waits for input before giving up
+    /// so the app can draw the next frame anyway. -1 waits for as long as
+    /// it takes, which is the cheapest for a form that only changes on
+    /// input, 0 does not wait at all. Can be changed between the frames,
+    /// e.g. to a short timeout only while something is animated.
+    pub fn set_poll_timeout(&mut self, ms: i32) {
+        if let Some(backend) = self.backend.as_mut() {
+            backend.set_timeout(ms);
+        }
+        self.poll_timeout = ms;
+    }
+
+    pub fn poll_timeout(&self) -> i32 {
+        self.poll_timeout
+    }
+
+    /// Takes effect starting from the next present(). The colors are
+    /// redefined under the same pair ids the widgets already use, so
+    /// everything on the screen switches to the new theme at once.
+    pub fn set_theme(&mut self, theme: Theme) {
+        if let Some(backend) = self.backend.as_mut() {
+            backend.apply_theme(&theme);
+        }
+        self.theme = theme;
+    }
+
+    /// The ids registered so far in the frame.
+    pub fn ids(&self) -> &[Id] {
+        &self.ids
+    }
+
+    /// The screen area of the widgets registered so far in the frame, in
+    /// the order they were drawn, clipped. See set_rect().
+    pub fn rects(&self) -> &[(Id, Rect)] {
+        &self.rects
+    }
+
+    /// Index of the hot widget among the ids of the last frame.
+    pub fn focus(&self) -> i32 {
+        self.focus
+    }
+
+    pub fn hot(&self) -> Option<Id> {
+        self.hot
+    }
+
+    /// The widget having the keyboard focus, kept across the frames even
+    /// while it is not drawn, unlike hot().
+    pub fn focused(&self) -> Option<Id> {
+        self.focused
+    }
+
+    /// Where the focused widget was drawn in this frame, before clipping.
+    pub fn focus_rect(&self) -> Option<Rect> {
+        self.focus_rect
+    }
+
+    pub fn viewport(&self) -> Rect {
+        self.viewport
+    }
+
+    pub fn theme(&self) -> &Theme {
+        &self.theme
+    }
+
+    /// How many colors the terminal has.
+    pub fn colors(&self) -> i16 {
+        self.colors
+    }
+
+    /// The number of the frame being built, counting from 0.
+    pub fn frame_number(&self) -> u64 {
+        self.frame
+    }
+
+    /// The bound keys with what they do, see bind().
+    pub fn bindings(&self) -> impl Iterator<Item = (Key, &str)> {
+        self.bindings.iter().map(|(key, _, help, _)| (*key, help.as_str()))
+    }
+
+    /// The same for the whole frame, so everything drawn in it agrees on
+    /// what time it is.
+    pub fn now(&self) -> Instant {
+        self.now.unwrap_or_else(Instant::now)
+    }
+
+    /// How long before the current frame `instant` was, rounded down to
+    /// the biggest unit: "just now", "42s ago", "5m ago", "2h ago", "3d ago".
+    pub fn time_ago(&self, instant: Instant) -> String {
+        let secs = self.now().saturating_duration_since(instant).as_secs();
+        match secs {
+            0..=9 => "just now".to_string(),
+            10..=59 => format!("{}s ago", secs),
+            60..=3599 => format!("{}m ago", secs / 60),
+            3600..=86399 => format!("{}h ago", secs / 3600),
+            _ => format!("{}d ago", secs / 86400),
+        }
+    }
+
+    /// While busy the input is dropped, so nothing gets activated by keys
+    /// pressed while the app could not react to them, and the UI is dimmed
+    /// unless disabled with ImTuiBuilder::with_dim_when_busy(). An app
+    /// blocking without drawing frames does not call getch(), so the keys
+    /// wait in the terminal instead: they are thrown away when the busy
+    /// state ends, rather than all landing at once in the next frame.
+    pub fn set_busy(&mut self, busy: bool) {
+        if self.busy && !busy {
+            if let Some(backend) = self.backend.as_mut() {
+                backend.flush_input();
+            }
+        }
+        self.busy = busy;
+    }
+
+    /// Whether the edges of every layout are drawn inverted, to see how
+    /// the space was divided.
+    pub fn set_debug_layout(&mut self, debug_layout: bool) {
+        self.debug_layout = debug_layout;
+    }
+
+    /// Whether the hot widget gets a marker next to it on top of being
+    /// drawn with HOT_PAIR.
+    pub fn set_focus_ring(&mut self, focus_ring: bool) {
+        self.focus_ring = focus_ring;
+    }
+
+    /// Best done between the frames: the widgets change their size with
+    /// the glyphs, so switching halfway would mix both in one layout.
+    pub fn set_glyphs(&mut self, glyphs: Glyphs) {
+        self.glyphs = glyphs;
+    }
+
+    pub fn glyphs(&self) -> Glyphs {
+        self.glyphs
+    }
+
+    /// Text being composed by an input method, shown underlined at the
+    /// caret of the active text input without being inserted into its
+    /// buffer. ncurses has no notion of input methods: usually the
+    /// terminal composes the text itself and only sends the committed
+    /// characters. This is for apps that run their own composition and
+    /// need to show its progress. Pass an empty string when done.
+    pub fn set_preedit(&mut self, preedit: &str) {
+        self.preedit.clear();
+        self.preedit.push_str(preedit);
+    }
+
+    pub fn preedit(&self) -> &str {
+        &self.preedit
+    }
+
+    /// Whether the currently active widget is a text input that consumes
+    /// printable keys. Apps should suppress their global hotkeys while
+    /// this is true.
+    pub fn text_input_active(&self) -> bool {
+        self.active.is_some() && self.active == self.text_input
+    }
+
+    /// activate() for a text input: until it is deactivated the widget `id`
+    /// gets the keys that would otherwise move the focus or fire the
+    /// bindings, see text_input_active().
+    pub fn set_text_input_active(&mut self, id: Id) {
+        self.activate(id);
+        self.text_input = Some(id);
+    }
+
+    /// Shows the hardware cursor at `pos` once the frame is presented, for
+    /// the caret of the active text input. Only the last one set in a
+    /// frame is shown.
+    pub fn set_caret(&mut self, pos: Point) {
+        self.caret = Some(pos);
+    }
+}
+
+/// How many columns `text` takes on the screen.
+pub fn text_width(text: &str) -> i32 {
+    chars_width(text.chars())
+}
+
+pub fn chars_width(chars: impl IntoIterator<Item = char>) -> i32 {
+    chars.into_iter().map(char_width).sum()
+}
+
+/// Two columns for the wide characters of East Asian scripts and the
+/// emoji, none for the combining marks, one for the rest. Only the common
+/// ranges are known, not the whole Unicode tables.
+pub fn char_width(x: char) -> i32 {
+    match x as u32 {
+        0x0300..=0x036F | 0x200B..=0x200F | 0x20D0..=0x20FF | 0xFE00..=0xFE0F => 0,
+        0x1100..=0x115F | 0x2E80..=0x303E | 0x3041..=0x33FF | 0x3400..=0x4DBF |
+        0x4E00..=0x9FFF | 0xA000..=0xA4CF | 0xAC00..=0xD7A3 | 0xF900..=0xFAFF |
+        0xFE30..=0xFE4F | 0xFF00..=0xFF60 | 0xFFE0..=0xFFE6 | 0x1F300..=0x1F64F |
+        0x1F900..=0x1F9FF | 0x20000..=0x3FFFD => 2,
+        _ => 1,
+    }
+}
+
+/// The part of `text` from column `from` to column `to`, without the wide
+/// characters cut by either end.
+pub fn clip_columns(text: &str, from: i32, to: i32) -> &str {
+    let mut column = 0;
+    let mut begin = text.len();
+    let mut end = text.len();
+    for (i, x) in text.char_indices() {
+        if column >= from && begin == text.len() {
+            begin = i;
+        }
+        column += char_width(x);
+        if column > to {
+            end = i;
+            break;
+        }
+    }
+    &text[cmp::min(begin, end)..end]
+}
+
+// The sizes the widgets report to the layout, computed without drawing
+// anything, so the layout can be planned before committing to positions.
+// The widgets use them too, so measured and drawn sizes never disagree.
+//
+// The widgets only displaying something take the size of their content,
+// so an empty label is 0 columns wide. The input widgets take the size
+// they reserve for the input no matter what is in it.
+
+pub fn measure_label(text: &str) -> Point {
+    Point(text_width(text), 1)
+}
+
+pub fn measure_label_min_width(text: &str, min_width: i32) -> Point {
+    Point(cmp::max(text_width(text), min_width), 1)
+}
+
+pub fn measure_button(glyphs: &Glyphs, label: &str) -> Point {
+    Point(chars_width(glyphs.button(label)), 1)
+}
+
+pub fn measure_checkbox(glyphs: &Glyphs, text: &str) -> Point {
+    Point(chars_width(glyphs.checkbox(false, text)), 1)
+}
+
+pub fn measure_radio(glyphs: &Glyphs, text: &str) -> Point {
+    Point(chars_width(glyphs.radio(false, text)), 1)
+}
+
+/// As wide as the widest value so it does not change with the value.
+pub fn measure_slider(range: &RangeInclusive<i32>) -> Point {
+    let value_width = cmp::max(text_width(&range.start().to_string()), text_width(&range.end().to_string()));
+    Point(SLIDER_WIDTH + 3 + value_width, 1)
+}
+
+pub fn measure_segmented(options: &[&str]) -> Point {
+    let separators = cmp::max(options.len() as i32 - 1, 0) * 3;
+    Point(options.iter().map(|option| text_width(option)).sum::<i32>() + separators + 4, 1)
+}
+
+pub fn label(imtui: &mut ImTui, text: &str) {
+    label_min_width(imtui, text, 0);
+}
+
+/// label() taking at least `min_width` columns, for the values changing
+/// every frame so whatever comes after them in the layout stays in place.
+pub fn label_min_width(imtui: &mut ImTui, text: &str, min_width: i32) {
+    let pos = imtui.free_pos();
+    imtui.put_str(pos, text, INACTIVE_PAIR);
+    imtui.add_size(measure_label_min_width(text, min_width));
+}
+
+/// label() with the occurrences of `term` drawn with `highlight_pair`.
+/// The matching ignores the case and goes from left to right, a match
+/// starting only after the previous one ended, so "aa" in "aaa" is
+/// highlighted once. An empty term matches nothing.
+pub fn highlight_label(imtui: &mut ImTui, text: &str, term: &str, highlight_pair: i16) {
+    let pos = imtui.free_pos();
+
+    let same = |a: char, b: char| a.to_lowercase().eq(b.to_lowercase());
+    let matches_at = |i: usize| {
+        let mut chars = text[i..].chars();
+        term.chars().all(|b| chars.next().is_some_and(|a| same(a, b)))
+    };
+    let term_len = term.chars().count();
+
+    // In bytes of `text` and in columns from its start.
+    let (mut start, mut start_n) = (0, 0);
+    let (mut i, mut n) = (0, 0);
+    while !term.is_empty() && i < text.len() {
+        if matches_at(i) {
+            let end = text[i..].char_indices().nth(term_len).map_or(text.len(), |(j, _)| i + j);
+            imtui.put_str(pos + Point(start_n, 0), &text[start..i], INACTIVE_PAIR);
+            imtui.put_str(pos + Point(n, 0), &text[i..end], highlight_pair);
+            n += text_width(&text[i..end]);
+            i = end;
+            start = i;
+            start_n = n;
+        } else {
+            let x = text[i..].chars().next().unwrap();
+            i += x.len_utf8();
+            n += char_width(x);
+        }
+    }
+    imtui.put_str(pos + Point(start_n, 0), &text[start..], INACTIVE_PAIR);
+
+    imtui.add_size(measure_label(text));
+}
+
+/// If `disabled` the widgets until the matching end_disabled() are dimmed,
+/// skipped by the focus navigation and the mouse, and report nothing, as
+/// if they were labels. The scopes nest: a widget is disabled if any of
+/// the scopes around it is, an inner begin_disabled(false) does not
+/// enable it back.
+pub fn begin_disabled(imtui: &mut ImTui, disabled: bool) {
+    imtui.disabled.push(disabled);
+}
+
+pub fn end_disabled(imtui: &mut ImTui) {
+    if imtui.disabled.pop().is_none() {
+        imtui.report_unbalanced(Scope::Disabled);
+    }
+}
+
+/// Breaks each paragraph of `text` into lines of words no wider than
+/// `width`. The words that do not fit on a line by themselves are broken
+/// wherever the line ends. Along with each line goes whether it is the
+/// last of its paragraph.
+pub fn wrap_words(text: &str, width: i32) -> Vec<(Vec<&str>, bool)> {
+    let mut lines = Vec::new();
+    for paragraph in text.split('\n') {
+        let mut line: Vec<&str> = Vec::new();
+        let mut line_width = 0;
+        for mut word in paragraph.split_whitespace() {
+            while width > 0 && text_width(word) > width {
+                if !line.is_empty() {
+                    lines.push((std::mem::take(&mut line), false));
+                    line_width = 0;
+                }
+                // At least one character, even if it is wider than `width`.
+                let head = match clip_columns(word, 0, width).len() {
+                    0 => byte_offset(word, 1),
+                    len => len,
+                };
+                let (head, tail) = word.split_at(head);
+                lines.push((vec![head], false));
+                word = tail;
+            }
+            let word_width = text_width(word);
+            if !line.is_empty() && line_width + 1 + word_width > width {
+                lines.push((std::mem::take(&mut line), false));
+                line_width = 0;
+            }
+            if !line.is_empty() {
+                line_width += 1;
+            }
+            line_width += word_width;
+            line.push(word);
+        }
+        lines.push((line, true));
+    }
+    lines
+}
+
+/// The words with the spaces between them widened to fill `width`, the
+/// leftmost gaps getting the extra ones.
+pub fn justify(words: &[&str], width: i32) -> String {
+    let gaps = words.len().saturating_sub(1);
+    let spaces = width - words.iter().map(|word| text_width(word)).sum::<i32>();
+    if gaps == 0 || spaces < gaps as i32 {
+        return words.join(" ");
+    }
+
+    let mut line = String::new();
+    for (i, word) in words.iter().enumerate() {
+        if i > 0 {
+            let extra = (i <= spaces as usize % gaps) as usize;
+            line.push_str(&" ".repeat(spaces as usize / gaps + extra));
+        }
+        line.push_str(word);
+    }
+    line
+}
+
+/// `text` word wrapped to `width`, the lines in a column. A newline starts
+/// a new paragraph. If `justify` the lines are stretched to exactly
+/// `width` by widening the spaces, except for the last line of each
+/// paragraph and the lines of a single word, which stay left-aligned.
+pub fn wrapped_label(imtui: &mut ImTui, text: &str, width: i32, justified: bool) {
+    let pos = imtui.free_pos();
+    let mut size = Point(0, 0);
+
+    for (words, last) in wrap_words(text, width) {
+        let line = if justified && !last {
+            justify(&words, width)
+        } else {
+            words.join(" ")
+        };
+        imtui.put_str(pos + Point(0, size.1), &line, INACTIVE_PAIR);
+        size = Point(cmp::max(size.0, text_width(&line)), size.1 + 1);
+    }
+
+    imtui.add_size(size);
+}
+
+/// Takes `size` in the layout without drawing anything, e.g. for some
+/// empty rows between the sections.
+pub fn spacer(imtui: &mut ImTui, size: Point) {
+    imtui.add_size(size);
+}
+
+/// A horizontal line `width` columns long. The layout it is in only gets
+/// its width at the end of the frame, so it is up to the caller, like for
+/// fill_row().
+pub fn separator(imtui: &mut ImTui, width: i32) {
+    let pos = imtui.free_pos();
+    let width = cmp::max(width, 0);
+    let line: String = std::iter::repeat_n('─', width as usize).collect();
+    imtui.put_str(pos, &line, INACTIVE_PAIR);
+    imtui.add_size(Point(width, 1));
+}
+
+/// A row of `width` empty cells with the background of `pair`, e.g. for
+/// a colored bar separating the sections.
+pub fn fill_row(imtui: &mut ImTui, pair: i16, width: i32) {
+    let pos = imtui.free_pos();
+    imtui.fill(Rect::new(pos, Point(width, 1)), pair);
+    imtui.add_size(Point(width, 1));
+}
+
+/// The press behavior shared by all the button-like widgets: Enter on the
+/// hot widget makes it active for one frame, and the frame after that it
+/// reports a click. A key bound to the widget clicks it right away.
+/// Returns whether it was clicked and the pair to draw the widget with.
+pub fn clickable(imtui: &mut ImTui, id: Id) -> (bool, i16) {
+    clickable_with_pair(imtui, INACTIVE_PAIR, id)
+}
+
+/// clickable() drawn with `inactive_pair` instead of INACTIVE_PAIR while
+/// it is neither hot, active nor hovered. A disabled widget is drawn with
+/// DISABLED_PAIR instead.
+pub fn clickable_with_pair(imtui: &mut ImTui, inactive_pair: i16, id: Id) -> (bool, i16) {
+    let mut clicked = false;
+    let mut pair = if imtui.is_disabled() {DISABLED_PAIR} else {inactive_pair};
+
+    // The key bound to a disabled widget is still taken by it, but only to
+    // be refused.
+    if imtui.is_disabled() && imtui.fired == Some(id) {
+        imtui.reject();
+    }
+
+    if imtui.is_active(id) {
+        imtui.deactivate();
+        clicked = true;
+    } else if imtui.fired(id) {
+        clicked = true;
+    } else if imtui.is_hot(id) {
+        pair = HOT_PAIR;
+        if !imtui.any_active() && imtui.key() == Some(Key::Enter) {
+            imtui.activate(id);
+            pair = ACTIVE_PAIR;
+        }
+    } else if imtui.is_hovered(id) {
+        pair = HOVERED_PAIR;
+    }
+
+    imtui.register_id(id);
+
+    (clicked, pair)
+}
+
+pub fn checkbox(imtui: &mut ImTui, text: &str, state: &mut bool, my_id: Id) -> bool {
+    let (clicked, pair) = clickable(imtui, my_id);
+
+    if clicked {
+        *state = !*state;
+        imtui.push_action(Action::Toggled(my_id, *state));
+    }
+
+    let pos = imtui.free_pos();
+
+    let glyphs = imtui.glyphs();
+    let width = chars_width(glyphs.checkbox(*state, text));
+    imtui.put_chars(pos, glyphs.checkbox(*state, text), width, pair, A_NORMAL());
+
+    let size = measure_checkbox(&glyphs, text);
+    imtui.add_size(size);
+
+    clicked
+}
+
+/// checkbox() that, unless `enabled`, is drawn with DISABLED_PAIR and
+/// cannot be toggled. See button_enabled().
+pub fn checkbox_enabled(imtui: &mut ImTui, text: &str, state: &mut bool, enabled: bool, id: Id) -> bool {
+    begin_disabled(imtui, !enabled);
+    let clicked = checkbox(imtui, text, state, id);
+    end_disabled(imtui);
+    clicked
+}
+
+/// Returns true when clicked, it is up to the caller to make it the
+/// `selected` one. See radio_group().
+pub fn radio(imtui: &mut ImTui, text: &str, selected: bool, id: Id) -> bool {
+    let (clicked, pair) = clickable(imtui, id);
+
+    let pos = imtui.free_pos();
+
+    let glyphs = imtui.glyphs();
+    let width = chars_width(glyphs.radio(selected, text));
+    imtui.put_chars(pos, glyphs.radio(selected, text), width, pair, A_NORMAL());
+
+    let size = measure_radio(&glyphs, text);
+    imtui.add_size(size);
+
+    clicked
+}
+
+/// A column of radios, one per item, with the `*selected` one marked.
+/// Returns true when another item got selected.
+pub fn radio_group(imtui: &mut ImTui, items: &[&str], selected: &mut usize, id: Id) -> bool {
+    let mut changed = false;
+
+    imtui.begin_layout(LayoutType::Vert, 0);
+    for (i, item) in items.iter().enumerate() {
+        if radio(imtui, item, i == *selected, id.child(i)) && i != *selected {
+            *selected = i;
+            changed = true;
+            imtui.push_action(Action::Changed(id));
+        }
+    }
+    imtui.end_layout();
+
+    changed
+}
+
+/// radio_group() with one item per variant of T, so there is no list of
+/// names to keep in sync with the enum.
+#[cfg(feature = "strum")]
+pub fn enum_select<T: IntoEnumIterator + PartialEq + std::fmt::Display>(imtui: &mut ImTui, value: &mut T, id: Id) -> bool {
+    let mut changed = false;
+
+    imtui.begin_layout(LayoutType::Vert, 0);
+    for (i, variant) in T::iter().enumerate() {
+        let selected = variant == *value;
+        if radio(imtui, &variant.to_string(), selected, id.child(i)) && !selected {
+            *value = variant;
+            changed = true;
+            imtui.push_action(Action::Changed(id));
+        }
+    }
+    imtui.end_layout();
+
+    changed
+}
+
+const COMBO_BOX_HEIGHT: usize = 8;
+
+/// The `*selected` option like `Canada  ▼`, as wide as the widest option.
+/// Enter opens the list of the options under it, at most COMBO_BOX_HEIGHT
+/// rows and above it if there is more room there, where Up and Down move the
+/// highlight and Enter selects the highlighted option, returning true. Esc
+/// closes the list leaving the selection as it was.
+///
+/// The open list goes on the layer above the combo box, so it covers the
+/// widgets drawn after it without deferring anything to the end of the
+/// frame: present() draws the layers in order anyway. It is not clipped to
+/// the layout the combo box is in and takes no room in it.
+pub fn combo_box(imtui: &mut ImTui, options: &[&str], selected: &mut usize, id: Id) -> bool {
+    // The highlighted option and the first one shown while open.
+    let (mut highlight, mut scroll) = imtui.take_state::<(usize, usize)>(id);
+    let mut changed = false;
+    let mut pair = INACTIVE_PAIR;
+
+    // The options may have changed since the last frame. The list closes
+    // once there are none left.
+    if imtui.is_active(id) && options.is_empty() {
+        imtui.deactivate();
+    }
+    highlight = cmp::min(highlight, options.len().saturating_sub(1));
+
+    if imtui.is_active(id) {
+        pair = ACTIVE_PAIR;
+        match imtui.key() {
+            Some(Key::Up) if highlight == 0 => imtui.reject(),
+            Some(Key::Down) if highlight + 1 >= options.len() => imtui.reject(),
+            Some(Key::Up) => highlight -= 1,
+            Some(Key::Down) => highlight += 1,
+            Some(Key::Enter) => {
+                imtui.deactivate();
+                if highlight < options.len() && highlight != *selected {
+                    *selected = highlight;
+                    changed = true;
+                    imtui.push_action(Action::Changed(id));
+                }
+            }
+            _ => {}
+        }
+    } else if imtui.is_hot(id) {
+        pair = HOT_PAIR;
+        if !imtui.any_active() && imtui.key() == Some(Key::Enter) && !options.is_empty() {
+            imtui.activate(id);
+            highlight = cmp::min(*selected, options.len() - 1);
+            scroll = 0;
+            pair = ACTIVE_PAIR;
+        }
+    } else if imtui.is_hovered(id) {
+        pair = HOVERED_PAIR;
+    }
+
+    imtui.register_id(id);
+    let pos = imtui.free_pos();
+
+    let width = options.iter().map(|x| text_width(x)).max().unwrap_or(0);
+    let current = options.get(*selected).cloned().unwrap_or("");
+    let pad = " ".repeat(cmp::max(width - text_width(current), 0) as usize);
+    imtui.put_str(pos, &format!("{}{} ▼", current, pad), pair);
+
+    if imtui.is_active(id) {
+        let Rect {pos: vpos, size: vsize} = imtui.viewport();
+        let room_below = cmp::max(vpos.1 + vsize.1 - pos.1 - 1, 0) as usize;
+        let room_above = cmp::max(pos.1 - vpos.1, 0) as usize;
+        let mut height = cmp::min(options.len(), COMBO_BOX_HEIGHT);
+        let below = height <= room_below || room_below >= room_above;
+        height = cmp::min(height, if below {room_below} else {room_above});
+        let top = if below {pos.1 + 1} else {pos.1 - height as i32};
+
+        if highlight < scroll {
+            scroll = highlight;
+        } else if highlight >= scroll + height {
+            scroll = highlight + 1 - height;
+        }
+        scroll = cmp::min(scroll, options.len() - height);
+
+        imtui.push_clip_viewport();
+        imtui.push_layer(imtui.layer() + 1);
+        for row in 0..height {
+            let i = scroll + row;
+            let item_pair = if i == highlight {HOT_PAIR} else {INACTIVE_PAIR};
+            imtui.put_str_fill(Point(pos.0, top + row as i32), options[i], item_pair, width + 1);
+        }
+        if options.len() > height {
+            scrollbar(imtui, Point(pos.0 + width + 1, top), height as i32, scroll, options.len());
+        }
+        imtui.pop_layer();
+        imtui.pop_clip();
+    }
+
+    imtui.put_state(id, (highlight, scroll));
+    imtui.add_size(Point(width + 2, 1));
+
+    changed
+}
+
+/// The options joined in one row like `[ Day | Week | Month ]`, with the
+/// `*selected` one in ACTIVE_PAIR. Unlike radio_group() it is a single
+/// widget: while focused Left and Right select the neighbouring option,
+/// returning true.
+pub fn segmented(imtui: &mut ImTui, options: &[&str], selected: &mut usize, id: Id) -> bool {
+    let focused = imtui.is_hot(id);
+    let mut changed = false;
+
+    if focused && !imtui.any_active() {
+        match imtui.key() {
+            Some(Key::Left) if *selected == 0 => imtui.reject(),
+            Some(Key::Right) if *selected + 1 >= options.len() => imtui.reject(),
+            Some(Key::Left) => {
+                *selected -= 1;
+                changed = true;
+            }
+            Some(Key::Right) => {
+                *selected += 1;
+                changed = true;
+            }
+            _ => {}
+        }
+    }
+    if changed {
+        imtui.push_action(Action::Changed(id));
+    }
+
+    imtui.register_id(id);
+    let pos = imtui.free_pos();
+    let pair = if focused {HOT_PAIR} else {INACTIVE_PAIR};
+
+    let mut x = 0;
+    imtui.put_str(pos, "[ ", pair);
+    x += 2;
+    for (i, option) in options.iter().enumerate() {
+        if i > 0 {
+            imtui.put_str(pos + Point(x, 0), " | ", pair);
+            x += 3;
+        }
+        let option_pair = if i == *selected {ACTIVE_PAIR} else {pair};
+        imtui.put_str(pos + Point(x, 0), option, option_pair);
+        x += text_width(option);
+    }
+    imtui.put_str(pos + Point(x, 0), " ]", pair);
+
+    imtui.add_size(measure_segmented(options));
+
+    changed
+}
+
+pub fn button(imtui: &mut ImTui, label: &str, id: Id) -> bool {
+    button_with_pair(imtui, label, INACTIVE_PAIR, id)
+}
+
+/// button() in its own colors, e.g. SWATCH_PAIR + COLOR_GREEN or a pair
+/// the app set up with init_pair(), while it is not focused. The hot and
+/// active ones stay the same as for every other widget.
+pub fn button_with_pair(imtui: &mut ImTui, label: &str, inactive_pair: i16, id: Id) -> bool {
+    button_underlined(imtui, label, inactive_pair, None, id)
+}
+
+/// button() that, unless `enabled`, is drawn with DISABLED_PAIR, is
+/// skipped by the focus navigation and the mouse and never reports a
+/// click, as in a begin_disabled() scope of its own.
+pub fn button_enabled(imtui: &mut ImTui, label: &str, enabled: bool, id: Id) -> bool {
+    begin_disabled(imtui, !enabled);
+    let clicked = button(imtui, label, id);
+    end_disabled(imtui);
+    clicked
+}
+
+// button_with_pair() with the character at the byte `underline` of the
+// label underlined, if any.
+fn button_underlined(imtui: &mut ImTui, label: &str, inactive_pair: i16, underline: Option<usize>, id: Id) -> bool {
+    let (clicked, pair) = clickable_with_pair(imtui, inactive_pair, id);
+
+    if clicked {
+        imtui.push_action(Action::Activated(id));
+    }
+
+    let pos = imtui.free_pos();
+
+    let glyphs = imtui.glyphs();
+    let size = measure_button(&glyphs, label);
+    imtui.put_chars(pos, glyphs.button(label), size.0, pair, A_NORMAL());
+    if let Some((i, letter)) = underline.and_then(|i| label[i..].chars().next().map(|x| (i, x))) {
+        let x = text_width(glyphs.button.0) + text_width(&label[..i]);
+        imtui.put_chars(pos + Point(x, 0), Some(letter), char_width(letter), pair, A_UNDERLINE());
+    }
+    imtui.add_size(size);
+
+    clicked
+}
+
+/// button() with the id derived from the label, see `impl From<&str> for Id`.
+pub fn button_labeled(imtui: &mut ImTui, label: &str) -> bool {
+    button(imtui, label, Id::from(label))
+}
+
+/// button() clicked by `key` too, wherever the focus is, unless another
+/// widget is active, see ImTui::bind(). The key is bound once the button
+/// is drawn and as long as it is drawn. The first
+/// letter of the label that is the key, in either case, is underlined.
+pub fn button_with_key(imtui: &mut ImTui, label: &str, key: Key, id: Id) -> bool {
+    imtui.bind_widget(key, id, label);
+
+    let underline = match key {
+        Key::Char(x) => label.char_indices().find(|(_, y)| y.to_lowercase().eq(x.to_lowercase())).map(|(i, _)| i),
+        _ => None,
+    };
+    button_underlined(imtui, label, INACTIVE_PAIR, underline, id)
+}
+
+/// Where truncate() cuts the text that does not fit.
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub enum Truncation {
+    End,
+    Middle,
+}
+
+/// `text` cut down to at most `width` columns with `…` in place of what
+/// was cut.
+pub fn truncate(text: &str, width: i32, truncation: Truncation) -> String {
+    if text_width(text) <= width {
+        return text.to_string();
+    }
+    if width <= 0 {
+        return String::new();
+    }
+
+    // In columns, a wide character that does not fit whole is left out.
+    let keep = width - 1;
+    let (head, tail) = match truncation {
+        Truncation::End => (keep, 0),
+        Truncation::Middle => (keep - keep / 2, keep / 2),
+    };
+    let mut tail_start = text.len();
+    let mut tail_width = 0;
+    for (i, x) in text.char_indices().rev() {
+        tail_width += char_width(x);
+        if tail_width > tail {
+            break;
+        }
+        tail_start = i;
+    }
+    let mut result = clip_columns(text, 0, head).to_string();
+    result.push('…');
+    result.push_str(&text[tail_start..]);
+    result
+}
+
+/// button() taking at most `max_width` columns, brackets included. The
+/// label is truncated to fit, down to nothing if there is just enough
+/// room for the brackets, which are always drawn.
+pub fn button_truncated(imtui: &mut ImTui, label: &str, max_width: i32, truncation: Truncation, id: Id) -> bool {
+    let chrome = measure_button(&imtui.glyphs(), "").0;
+    button(imtui, &truncate(label, max_width - chrome, truncation), id)
+}
+
+/// Text looking like a label, underlined to tell it apart, that is
+/// focused and activated like a button.
+pub fn link(imtui: &mut ImTui, text: &str, id: Id) -> bool {
+    let (clicked, pair) = clickable(imtui, id);
+
+    if clicked {
+        imtui.push_action(Action::Activated(id));
+    }
+
+    let pos = imtui.free_pos();
+    imtui.put_str_attr(pos, text, pair, A_UNDERLINE());
+    imtui.add_size(measure_label(text));
+
+    clicked
+}
+
+/// A row of a form: `text` right aligned in `label_width` columns and the
+/// field made by `field` after it. With the same label_width for every
+/// row the fields line up whatever the length of their labels.
+pub fn form_row<R>(imtui: &mut ImTui, text: &str, label_width: i32, field: impl FnOnce(&mut ImTui) -> R) -> R {
+    imtui.begin_layout(LayoutType::Horz, 1);
+    let pad = cmp::max(label_width - text_width(text), 0) as usize;
+    label(imtui, &format!("{}{}", " ".repeat(pad), text));
+    let result = field(imtui);
+    imtui.end_layout();
+    result
+}
+
+/// A trail of links like `Home › Contacts › Bob`, returning the index of
+/// the segment clicked, e.g. to go back up to it.
+pub fn breadcrumbs(imtui: &mut ImTui, segments: &[&str], base_id: Id) -> Option<usize> {
+    let mut clicked = None;
+
+    imtui.begin_layout(LayoutType::Horz, 1);
+    for (i, segment) in segments.iter().enumerate() {
+        if i > 0 {
+            let pos = imtui.free_pos();
+            imtui.put_str_attr(pos, "›", INACTIVE_PAIR, A_DIM());
+            imtui.add_size(measure_label("›"));
+        }
+        if link(imtui, segment, base_id.child(i)) {
+            clicked = Some(i);
+        }
+    }
+    imtui.end_layout();
+
+    clicked
+}
+
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub enum DialogResult {
+    Ok,
+    Cancel,
+}
+
+/// The row of OK and Cancel buttons at the bottom of a dialog. The OK one
+/// gets the focus in the first frame the row is drawn after not being
+/// drawn, so Enter confirms a dialog that just opened. Esc cancels it, but
+/// only when no widget is active: an Esc while editing a field of the
+/// dialog just leaves the field, like anywhere else, and takes another.
+pub fn dialog_buttons(imtui: &mut ImTui, ok_label: &str, cancel_label: &str, base_id: Id) -> Option<DialogResult> {
+    let ok_id = base_id.child(0);
+    let cancel_id = base_id.child(1);
+    let mut result = None;
+
+    let last_drawn = imtui.take_state::<Option<u64>>(base_id);
+    if last_drawn.map(|frame| frame + 1) != Some(imtui.frame_number()) {
+        imtui.set_focus(ok_id);
+    }
+    imtui.put_state(base_id, Some(imtui.frame_number()));
+
+    if !imtui.any_active() && imtui.key() == Some(Key::Esc) {
+        result = Some(DialogResult::Cancel);
+    }
+
+    imtui.begin_layout(LayoutType::Horz, 1);
+    if button(imtui, ok_label, ok_id) {
+        result = Some(DialogResult::Ok);
+    }
+    if button(imtui, cancel_label, cancel_id) {
+        result = Some(DialogResult::Cancel);
+    }
+    imtui.end_layout();
+
+    result
+}
+
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub enum RowAction {
+    Edit,
+    Delete,
+}
+
+/// One row of a list with its own edit and delete links. The ids are
+/// derived from base_id and index, so they stay put across frames. Only
+/// the requested action is returned, the caller applies it once it's
+/// done iterating so removing the row does not shift the ones after it.
+pub fn contact_row(imtui: &mut ImTui, index: usize, first: &str, last: &str, base_id: Id) -> Option<RowAction> {
+    let row_id = base_id.child(index);
+    let mut action = None;
+
+    imtui.begin_layout(LayoutType::Horz, 1);
+    {
+        label_min_width(imtui, &format!("{} | {}", first, last), 18);
+
+        if link(imtui, "[edit]", row_id.child(0)) {
+            action = Some(RowAction::Edit);
+        }
+
+        if link(imtui, "[x]", row_id.child(1)) {
+            action = Some(RowAction::Delete);
+        }
+    }
+    imtui.end_layout();
+
+    action
+}
+
+/// Opens a section with a header that toggles `*open` when activated.
+/// Returns whether the section is open so the caller can skip building
+/// the hidden children. Must always be paired with end_collapsible(),
+/// regardless of the returned value.
+pub fn begin_collapsible(imtui: &mut ImTui, title: &str, open: &mut bool, id: Id) -> bool {
+    imtui.begin_layout(LayoutType::Vert, 0);
+
+    let (clicked, pair) = clickable(imtui, id);
+
+    if clicked {
+        *open = !*open;
+        imtui.push_action(Action::Toggled(id, *open));
+    }
+
+    let pos = imtui.free_pos();
+
+    let text = format!("{} {}", if *open {"▾"} else {"▸"}, title);
+    imtui.put_str(pos, &text, pair);
+
+    imtui.add_size(Point(text_width(&text), 1));
+
+    *open
+}
+
+pub fn end_collapsible(imtui: &mut ImTui) {
+    imtui.end_layout();
+}
+
+/// A line of a tree, indented by `depth`. Enter toggles `*open`, Right
+/// opens and Left closes it. Returns whether the node is open, the caller
+/// draws the children with depth + 1 only then. Unlike a collapsible there
+/// is no layout to close, the nodes just follow each other.
+pub fn tree_node(imtui: &mut ImTui, text: &str, open: &mut bool, depth: i32, id: Id) -> bool {
+    let (clicked, pair) = clickable(imtui, id);
+
+    let was_open = *open;
+    if clicked {
+        *open = !*open;
+    } else if imtui.is_hot(id) && !imtui.any_active() {
+        match imtui.key() {
+            Some(Key::Right) => *open = true,
+            Some(Key::Left) => *open = false,
+            _ => {}
+        }
+    }
+    if *open != was_open {
+        imtui.push_action(Action::Toggled(id, *open));
+    }
+
+    let indent = cmp::max(depth, 0) * 2;
+    let pos = imtui.free_pos() + Point(indent, 0);
+
+    let text = format!("{} {}", if *open {"▾"} else {"▸"}, text);
+    imtui.put_str(pos, &text, pair);
+
+    imtui.add_size(Point(indent + text_width(&text), 1));
+
+    *open
+}
+
+struct Split {
+    typ: LayoutType,
+    pos: Point,
+    size: Point,
+    divider: i32,
+}
+
+/// Divides the space left in the current clipping region into two panes
+/// at `*ratio`: side by side for LayoutType::Horz, stacked for
+/// LayoutType::Vert. The divider between them is focusable; it can be
+/// grabbed with Enter and moved with the arrow keys, or dragged with the
+/// mouse. The content of the first pane goes between begin_split() and
+/// next_split(), the second one between next_split() and end_split().
+pub fn begin_split(imtui: &mut ImTui, ratio: &mut f32, typ: LayoutType, id: Id) {
+    let pos = imtui.free_pos();
+    let clip = imtui.clip_rect();
+    let size = clip.pos + clip.size - pos;
+    let size = Point(cmp::max(size.0, 0), cmp::max(size.1, 0));
+    let extent = match typ {
+        LayoutType::Horz => size.0,
+        LayoutType::Vert => size.1,
+    };
+
+    let mut pair = INACTIVE_PAIR;
+    if imtui.is_active(id) {
+        pair = ACTIVE_PAIR;
+        let step = 1.0 / cmp::max(extent - 1, 1) as f32;
+        match (&typ, imtui.key()) {
+            (_, Some(Key::Enter)) => imtui.deactivate(),
+            (LayoutType::Horz, Some(Key::Left)) | (LayoutType::Vert, Some(Key::Up)) => *ratio -= step,
+            (LayoutType::Horz, Some(Key::Right)) | (LayoutType::Vert, Some(Key::Down)) => *ratio += step,
+            _ => {}
+        }
+
+        if let Some(MouseEvent {pos: mouse, kind}) = imtui.mouse() {
+            let offset = match typ {
+                LayoutType::Horz => mouse.0 - pos.0,
+                LayoutType::Vert => mouse.1 - pos.1,
+            };
+            *ratio = offset as f32 / cmp::max(extent - 1, 1) as f32;
+            if kind == MouseKind::Release {
+                imtui.deactivate();
+            }
+        }
+    } else if imtui.is_hot(id) {
+        pair = HOT_PAIR;
+        if !imtui.any_active() && imtui.key() == Some(Key::Enter) {
+            imtui.activate(id);
+        }
+    } else if imtui.is_hovered(id) {
+        pair = HOVERED_PAIR;
+    }
+    *ratio = ratio.clamp(0.0, 1.0);
+
+    let divider = (*ratio * cmp::max(extent - 1, 0) as f32).round() as i32;
+    let divider_rect = match typ {
+        LayoutType::Horz => Rect::new(pos + Point(divider, 0), Point(1, size.1)),
+        LayoutType::Vert => Rect::new(pos + Point(0, divider), Point(size.0, 1)),
+    };
+
+    if !imtui.any_active() {
+        if let Some(MouseEvent {pos: mouse, kind: MouseKind::Press}) = imtui.mouse() {
+            if divider_rect.contains(mouse) {
+                imtui.activate(id);
+                pair = ACTIVE_PAIR;
+            }
+        }
+    }
+
+    imtui.register_id(id);
+    imtui.set_rect(id, divider_rect);
+
+    match typ {
+        LayoutType::Horz => for y in 0..size.1 {
+            imtui.put_str(divider_rect.pos + Point(0, y), "│", pair);
+        },
+        LayoutType::Vert => {
+            let line = "─".repeat(size.0 as usize);
+            imtui.put_str(divider_rect.pos, &line, pair);
+        }
+    }
+
+    let first = match typ {
+        LayoutType::Horz => Point(divider, size.1),
+        LayoutType::Vert => Point(size.0, divider),
+    };
+    imtui.push_clip(Rect::new(pos, first));
+    imtui.begin_layout_at(LayoutType::Vert, pos, 0);
+    imtui.splits.push(Split {typ, pos, size, divider});
+}
+
+pub fn next_split(imtui: &mut ImTui) {
+    if imtui.splits.is_empty() {
+        return imtui.report_unbalanced(Scope::Split);
+    }
+    imtui.end_layout_at();
+    imtui.pop_clip();
+
+    let split = &imtui.splits[imtui.splits.len() - 1];
+    let (pos, size) = match split.typ {
+        LayoutType::Horz => (
+            split.pos + Point(split.divider + 1, 0),
+            Point(split.size.0 - split.divider - 1, split.size.1),
+        ),
+        LayoutType::Vert => (
+            split.pos + Point(0, split.divider + 1),
+            Point(split.size.0, split.size.1 - split.divider - 1),
+        ),
+    };
+    imtui.push_clip(Rect::new(pos, size));
+    imtui.begin_layout_at(LayoutType::Vert, pos, 0);
+}
+
+pub fn end_split(imtui: &mut ImTui) {
+    let split = match imtui.splits.pop() {
+        Some(split) => split,
+        None => return imtui.report_unbalanced(Scope::Split),
+    };
+    imtui.end_layout_at();
+    imtui.pop_clip();
+    imtui.add_size(split.size);
+}
+
+#[derive(Default, Copy, Clone, PartialEq, Eq, Debug)]
+pub struct FrameStyle {
+    /// Empty cells between the border and the content on every side.
+    pub padding: i32,
+    /// Double line border instead of the single line one.
+    pub double: bool,
+}
+
+/// Draws a border around everything laid out until end_frame(). The
+/// reported size includes the border and the padding.
+pub fn begin_frame(imtui: &mut ImTui, style: FrameStyle) {
+    begin_frame_titled(imtui, style, "");
+}
+
+/// A frame with `title` in its top edge, until end_window(). The frame is
+/// made wide enough for the whole title.
+pub fn begin_window(imtui: &mut ImTui, title: &str) {
+    begin_frame_titled(imtui, FrameStyle::default(), title);
+}
+
+pub fn end_window(imtui: &mut ImTui) {
+    end_frame(imtui);
+}
+
+pub fn begin_frame_titled(imtui: &mut ImTui, style: FrameStyle, title: &str) {
+    let pos = imtui.free_pos();
+    let inset = 1 + cmp::max(style.padding, 0);
+    let start = imtui.frame_titles.len();
+    imtui.frame_titles.push_str(title);
+    imtui.frames.push((pos, style, start..imtui.frame_titles.len()));
+    imtui.begin_layout_at(LayoutType::Vert, pos + Point(inset, inset), 0);
+}
+
+pub fn end_frame(imtui: &mut ImTui) {
+    let (pos, style, range) = match imtui.frames.pop() {
+        Some(frame) => frame,
+        None => return imtui.report_unbalanced(Scope::Frame),
+    };
+    let content = match imtui.end_layout_at() {
+        Some(content) => content,
+        None => return,
+    };
+    let titles = std::mem::take(&mut imtui.frame_titles);
+    let title = &titles[range.clone()];
+    let inset = 1 + cmp::max(style.padding, 0);
+    let mut size = content + Point(inset * 2, inset * 2);
+    // Drawn as `┌ title ─┐`.
+    let space = if title.is_empty() {""} else {" "};
+    let title_width = text_width(title) + 2 * space.len() as i32;
+    size.0 = cmp::max(size.0, title_width + 3);
+
+    let (h, v, tl, tr, bl, br) = if style.double {
+        ('═', '║', '╔', '╗', '╚', '╝')
+    } else {
+        ('─', '│', '┌', '┐', '└', '┘')
+    };
+    let top = std::iter::once(tl)
+        .chain(space.chars()).chain(title.chars()).chain(space.chars())
+        .chain(std::iter::repeat_n(h, (size.0 - 2 - title_width) as usize))
+        .chain(std::iter::once(tr));
+    imtui.put_chars(pos, top, size.0, INACTIVE_PAIR, A_NORMAL());
+    for y in 1..size.1 - 1 {
+        imtui.put_chars(pos + Point(0, y), Some(v), 1, INACTIVE_PAIR, A_NORMAL());
+        imtui.put_chars(pos + Point(size.0 - 1, y), Some(v), 1, INACTIVE_PAIR, A_NORMAL());
+    }
+    let bottom = std::iter::once(bl)
+        .chain(std::iter::repeat_n(h, size.0 as usize - 2))
+        .chain(std::iter::once(br));
+    imtui.put_chars(pos + Point(0, size.1 - 1), bottom, size.0, INACTIVE_PAIR, A_NORMAL());
+
+    imtui.frame_titles = titles;
+    imtui.frame_titles.truncate(range.start);
+    imtui.add_size(size);
+}
+
+/// Lays out what `f` draws starting from `pos` on the screen instead of
+/// the free position of the current layout, which is left as it was:
+/// what is drawn there takes no room in it.
+pub fn at<R>(imtui: &mut ImTui, pos: Point, f: impl FnOnce(&mut ImTui) -> R) -> R {
+    imtui.begin_layout_at(LayoutType::Vert, pos, 0);
+    let result = f(imtui);
+    imtui.end_layout_at();
+    result
+}
+
+/// Lays out what `f` draws scrolled up by `*scroll` rows and clipped to the
+/// rest of the viewport, for an app taller than the terminal. PageUp and
+/// PageDown scroll it by a page, unless the focused widget takes the
+/// arrows (see ImTui::claim_up_down()) and so these keys too. Moving the
+/// focus to a widget out of sight scrolls it into view.
+pub fn scrolled<R>(imtui: &mut ImTui, scroll: &mut i32, id: Id, f: impl FnOnce(&mut ImTui) -> R) -> R {
+    let pos = imtui.free_pos();
+    let Rect {pos: vpos, size: vsize} = imtui.viewport();
+    let page = Point(vpos.0 + vsize.0 - pos.0, vpos.1 + vsize.1 - pos.1);
+    let page = Point(cmp::max(page.0, 0), cmp::max(page.1, 0));
+
+    imtui.push_clip(Rect::new(pos, page));
+    imtui.begin_layout_at(LayoutType::Vert, pos - Point(0, *scroll), 0);
+    let first = imtui.rects().len();
+    let result = f(imtui);
+    let content = imtui.end_layout_at().unwrap_or(Point(0, 0));
+    imtui.pop_clip();
+
+    if !imtui.any_active() && !imtui.up_down_claimed() {
+        match imtui.key() {
+            Some(Key::PageUp) => *scroll -= page.1,
+            Some(Key::PageDown) => *scroll += page.1,
+            _ => {}
+        }
+    }
+
+    // Only when the focus moves, so paging away from it is possible.
+    let last_focused = imtui.take_state::<Option<Id>>(id);
+    let inside = imtui.rects()[first..].iter().any(|(x, _)| Some(*x) == imtui.focused());
+    if let (Some(rect), true) = (imtui.focus_rect(), inside && imtui.focused() != last_focused) {
+        let top = rect.pos.1 - pos.1 + *scroll;
+        if top < *scroll {
+            *scroll = top;
+        } else if top + rect.size.1 > *scroll + page.1 {
+            *scroll = top + rect.size.1 - page.1;
+        }
+    }
+    imtui.put_state(id, imtui.focused());
+    *scroll = (*scroll).clamp(0, cmp::max(content.1 - page.1, 0));
+
+    imtui.add_size(Point(content.0, cmp::min(content.1, page.1)));
+    result
+}
+
+/// Shows `text` next to the mouse once it rested on the widget `id` for
+/// the delay set with ImTuiBuilder::with_tooltip_delay(), until it leaves
+/// it. Called right after the widget, every frame. The delay is only
+/// checked when a frame is drawn, so the tooltip waits for the next one
+/// if getch() blocks for longer, see ImTui::set_poll_timeout().
+pub fn tooltip(imtui: &mut ImTui, text: &str, id: Id) {
+    if imtui.hover_time(id).is_none_or(|time| time < imtui.tooltip_delay()) {
+        return;
+    }
+    let mouse = match imtui.mouse_pos() {
+        Some(mouse) => mouse,
+        None => return,
+    };
+
+    let size = Point(text_width(text) + 2, 1);
+    let Rect {pos: vpos, size: vsize} = imtui.viewport();
+    let pos = Point(
+        cmp::max(cmp::min(mouse.0 + 1, vpos.0 + vsize.0 - size.0), vpos.0),
+        cmp::max(cmp::min(mouse.1 + 1, vpos.1 + vsize.1 - 1), vpos.1),
+    );
+
+    // Not clipped to the container the widget is in.
+    imtui.push_clip_viewport();
+    imtui.push_layer(TOOLTIP_LAYER);
+    imtui.put_str(pos, &format!(" {} ", text), HOT_PAIR);
+    imtui.pop_layer();
+    imtui.pop_clip();
+}
+
+/// Draws the widgets made by `f` with `(count)` over their top right
+/// corner, on top of the layer they are on, e.g. for the number of unread
+/// messages. Nothing extra is drawn for a zero count, nor for widgets that
+/// do not register an id.
+pub fn with_badge<R>(imtui: &mut ImTui, count: usize, f: impl FnOnce(&mut ImTui) -> R) -> R {
+    let first = imtui.rects().len();
+    let result = f(imtui);
+
+    let right = imtui.rects()[first..].iter().map(|(_, rect)| rect.pos.0 + rect.size.0).max();
+    let top = imtui.rects()[first..].iter().map(|(_, rect)| rect.pos.1).min();
+    if let (Some(right), Some(top)) = (right, top) {
+        if count > 0 {
+            let badge = format!("({})", count);
+            imtui.push_layer(imtui.layer() + 1);
+            imtui.put_str(Point(right - text_width(&badge), top), &badge, ERROR_PAIR);
+            imtui.pop_layer();
+        }
+    }
+
+    result
+}
+
+/// A bar across the bottom row of the viewport like ` ^S Save  ^Q Quit`,
+/// the keys in ACTIVE_PAIR. It is not a part of the layout, so leave that
+/// row free. The hints that do not fit are cut off with `…`.
+pub fn key_hints(imtui: &mut ImTui, hints: &[(&str, &str)]) {
+    let Rect {pos: vpos, size: vsize} = imtui.viewport();
+    if vsize.1 <= 0 {
+        return;
+    }
+    let pos = vpos + Point(0, vsize.1 - 1);
+    imtui.fill(Rect::new(pos, Point(vsize.0, 1)), INACTIVE_PAIR);
+
+    let mut x = 1;
+    for (i, (key, help)) in hints.iter().enumerate() {
+        let width = text_width(key) + 1 + text_width(help);
+        let room = if i + 1 < hints.len() {vsize.0 - 1} else {vsize.0};
+        if x + width > room {
+            imtui.put_str(pos + Point(cmp::min(x, vsize.0 - 1), 0), "…", INACTIVE_PAIR);
+            break;
+        }
+        imtui.put_str(pos + Point(x, 0), key, ACTIVE_PAIR);
+        imtui.put_str(pos + Point(x + text_width(key) + 1, 0), help, INACTIVE_PAIR);
+        x += width + 2;
+    }
+}
+
+/// `text` in STATUS_PAIR across the bottom row of the viewport, wherever
+/// the layout is. Like key_hints() it takes no room in the layout, and the
+/// two go on the same row, so use only one of them.
+pub fn status_bar(imtui: &mut ImTui, text: &str) {
+    let Rect {pos: vpos, size: vsize} = imtui.viewport();
+    if vsize.1 <= 0 {
+        return;
+    }
+    let pos = vpos + Point(0, vsize.1 - 1);
+    imtui.put_str_fill(pos, &format!(" {}", text), STATUS_PAIR, vsize.0);
+}
+
+/// key_hints() of the bindings of ImTui.
+pub fn binding_hints(imtui: &mut ImTui) {
+    let hints: Vec<(String, String)> = imtui.bindings()
+        .map(|(key, help)| (key.to_string(), help.to_string()))
+        .collect();
+    let hints: Vec<(&str, &str)> = hints.iter()
+        .map(|(key, help)| (key.as_str(), help.as_str()))
+        .collect();
+    key_hints(imtui, &hints);
+}
+
+// The keys for moving the focus, always there, listed before the bindings
+// in the help_overlay().
+const NAVIGATION_HELP: [(Key, &str); 8] = [
+    (Key::Tab, "Focus the next widget"),
+    (Key::BackTab, "Focus the previous widget"),
+    (Key::Char('s'), "Focus the next widget"),
+    (Key::Char('w'), "Focus the previous widget"),
+    (Key::Down, "Focus the next widget"),
+    (Key::Up, "Focus the previous widget"),
+    (Key::Home, "Focus the first widget"),
+    (Key::End, "Focus the last widget"),
+];
+
+/// While `*open`, lists the navigation keys and the bindings of ImTui
+/// with what they do, in a frame centered in the viewport on top of
+/// everything else. Esc closes it. Should be called last in the frame, it
+/// is not a part of the layout.
+pub fn help_overlay(imtui: &mut ImTui, open: &mut bool) {
+    if *open && imtui.key() == Some(Key::Esc) {
+        *open = false;
+    }
+    if !*open {
+        return;
+    }
+    imtui.declare_modal();
+
+    let mut entries: Vec<(String, &str)> = NAVIGATION_HELP.iter()
+        .map(|(key, help)| (key.to_string(), *help))
+        .collect();
+    entries.extend(imtui.bindings().map(|(key, help)| (key.to_string(), help)));
+
+    let key_width = entries.iter().map(|(key, _)| text_width(key)).max().unwrap_or(0) as usize;
+    let lines: Vec<String> = entries.iter()
+        .map(|(key, help)| format!("{:width$}  {}", key, help, width = key_width))
+        .collect();
+
+    let style = FrameStyle {padding: 1, double: false};
+    let content = Point(lines.iter().map(|x| text_width(x)).max().unwrap_or(0), lines.len() as i32);
+    let size = content + Point(4, 4);
+    let Rect {pos: vpos, size: vsize} = imtui.viewport();
+    let pos = vpos + Point(cmp::max((vsize.0 - size.0) / 2, 0), cmp::max((vsize.1 - size.1) / 2, 0));
+
+    imtui.push_layer(1);
+    imtui.fill(Rect::new(pos, size), INACTIVE_PAIR);
+    at(imtui, pos, |imtui| {
+        begin_frame(imtui, style);
+        for line in &lines {
+            label(imtui, line);
+        }
+        end_frame(imtui);
+    });
+    imtui.pop_layer();
+}
+
+// Block glyphs indexed by how many eighths of a cell they fill.
+const BLOCKS: [char; 9] = [' ', '▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// Vertical bars, one column per value, scaled so the biggest value spans
+/// the whole `height`. Negative values are drawn as empty bars.
+pub fn bar_chart(imtui: &mut ImTui, values: &[f32], height: i32) {
+    let height = cmp::max(height, 0);
+    let pos = imtui.free_pos();
+
+    let max = values.iter().cloned().fold(0.0, f32::max);
+    if max > 0.0 {
+        let eighths: Vec<i32> = values.iter()
+            .map(|x| (x.max(0.0) / max * (height * 8) as f32).round() as i32)
+            .collect();
+
+        for row in 0..height {
+            let base = (height - 1 - row) * 8;
+            let line: String = eighths.iter()
+                .map(|x| BLOCKS[(x - base).clamp(0, 8) as usize])
+                .collect();
+            imtui.put_str(pos + Point(0, row), &line, INACTIVE_PAIR);
+        }
+    }
+
+    imtui.add_size(Point(values.len() as i32, height));
+}
+
+/// One row trend of `values` squeezed (by averaging) or stretched (by
+/// repeating) into `width` columns. The smallest value is drawn as the
+/// lowest glyph and the biggest one as the full block.
+pub fn sparkline(imtui: &mut ImTui, values: &[f32], width: i32) {
+    let width = cmp::max(width, 0);
+    let pos = imtui.free_pos();
+
+    if !values.is_empty() {
+        let n = values.len();
+        let buckets: Vec<f32> = (0..width as usize).map(|col| {
+            let begin = col * n / width as usize;
+            let end = cmp::max((col + 1) * n / width as usize, begin + 1);
+            values[begin..end].iter().sum::<f32>() / (end - begin) as f32
+        }).collect();
+
+        let min = buckets.iter().cloned().fold(f32::INFINITY, f32::min);
+        let max = buckets.iter().cloned().fold(f32::NEG_INFINITY, f32::max);
+        let line: String = buckets.iter().map(|x| {
+            let t = if max > min {(x - min) / (max - min)} else {0.0};
+            BLOCKS[1 + (t * 7.0).round().clamp(0.0, 7.0) as usize]
+        }).collect();
+        imtui.put_str(pos, &line, INACTIVE_PAIR);
+    }
+
+    imtui.add_size(Point(width, 1));
+}
+
+/// A bar like `[=====>    ]` with `fraction` of it filled, `width` columns
+/// including the brackets. The fractions beyond 0..1 count as the nearest
+/// end of it.
+pub fn progress_bar(imtui: &mut ImTui, fraction: f32, width: i32) {
+    progress_bar_impl(imtui, fraction, width, false);
+}
+
+/// progress_bar() with the percentage in the middle of it.
+pub fn progress_bar_with_percent(imtui: &mut ImTui, fraction: f32, width: i32) {
+    progress_bar_impl(imtui, fraction, width, true);
+}
+
+fn progress_bar_impl(imtui: &mut ImTui, fraction: f32, width: i32, percent: bool) {
+    let width = cmp::max(width, 0);
+    let fraction = if fraction.is_nan() {0.0} else {fraction.clamp(0.0, 1.0)};
+    let pos = imtui.free_pos();
+
+    if width >= 2 {
+        let inner = (width - 2) as usize;
+        let filled = cmp::min((fraction * inner as f32).round() as usize, inner);
+        let mut bar: Vec<char> = "=".repeat(filled).chars().chain(" ".repeat(inner - filled).chars()).collect();
+        if 0 < filled && filled < inner {
+            bar[filled - 1] = '>';
+        }
+        if percent {
+            let label = format!("{}%", (fraction * 100.0).round() as i32);
+            let start = inner.saturating_sub(label.len()) / 2;
+            for (i, x) in label.chars().enumerate().take(inner) {
+                bar[start + i] = x;
+            }
+        }
+        let bar: String = bar.into_iter().collect();
+        imtui.put_str(pos, &format!("[{}]", bar), INACTIVE_PAIR);
+    }
+
+    imtui.add_size(Point(width, 1));
+}
+
+pub struct LogViewState {
+    pub offset: usize,
+    /// Stick to the last line as the new ones come in.
+    pub at_bottom: bool,
+}
+
+impl Default for LogViewState {
+    fn default() -> Self {
+        Self {
+            offset: 0,
+            at_bottom: true,
+        }
+    }
+}
+
+/// Shows the tail of `lines` in a `size` region. While focused Up and
+/// PageUp, or the mouse wheel, scroll back in the history, which stops
+/// following the new lines until it is scrolled back down to the bottom.
+/// The last column is a scrollbar() once there are more lines than fit.
+pub fn log_view(imtui: &mut ImTui, lines: &[String], size: Point, state: &mut LogViewState, id: Id) {
+    let height = cmp::max(size.1, 0) as usize;
+    let max_offset = lines.len().saturating_sub(height);
+
+    let mut pair = INACTIVE_PAIR;
+    if imtui.is_hot(id) {
+        pair = HOT_PAIR;
+        if !imtui.any_active() {
+            match imtui.key() {
+                Some(Key::Up) | Some(Key::PageUp) if state.offset == 0 => imtui.reject(),
+                Some(Key::Down) | Some(Key::PageDown) if state.offset >= max_offset => imtui.reject(),
+                Some(Key::Up) => state.offset = state.offset.saturating_sub(1),
+                Some(Key::Down) => state.offset += 1,
+                Some(Key::PageUp) => state.offset = state.offset.saturating_sub(height),
+                Some(Key::PageDown) => state.offset += height,
+                _ => {}
+            }
+            match imtui.key() {
+                Some(Key::Up) | Some(Key::PageUp) => state.at_bottom = false,
+                Some(Key::Down) | Some(Key::PageDown) => state.at_bottom = state.offset >= max_offset,
+                _ => {}
+            }
+        }
+    } else if imtui.is_hovered(id) {
+        pair = HOVERED_PAIR;
+    }
+
+    let wheel = imtui.wheel(id);
+    if wheel < 0 {
+        state.offset = state.offset.saturating_sub(-wheel as usize);
+        state.at_bottom = false;
+    } else if wheel > 0 {
+        state.offset += wheel as usize;
+        state.at_bottom = state.offset >= max_offset;
+    }
+
+    if state.at_bottom {
+        state.offset = max_offset;
+    }
+    state.offset = cmp::min(state.offset, max_offset);
+
+    imtui.claim_up_down(id);
+    imtui.register_id(id);
+    let pos = imtui.free_pos();
+
+    let overflows = lines.len() > height;
+    let width = if overflows {size.0 - 1} else {size.0};
+
+    imtui.push_clip(Rect::new(pos, Point(width, size.1)));
+    for row in 0..height {
+        let line = lines.get(state.offset + row).map(|x| x.as_str()).unwrap_or("");
+        imtui.put_str_fill(pos + Point(0, row as i32), line, pair, width);
+    }
+    imtui.pop_clip();
+    if overflows {
+        scrollbar(imtui, pos + Point(width, 0), size.1, state.offset, lines.len());
+    }
+
+    imtui.add_size(size);
+}
+
+/// A vertical track of `height` cells at `pos` with a thumb as tall as
+/// the part of the `total` rows that is shown, starting at `offset`. Goes
+/// next to the content rather than into the layout, so it takes no space.
+/// Without a thumb if everything fits.
+pub fn scrollbar(imtui: &mut ImTui, pos: Point, height: i32, offset: usize, total: usize) {
+    if height <= 0 {
+        return;
+    }
+
+    let thumb = scrollbar_thumb(height as usize, offset, total, height as usize);
+    for y in 0..height {
+        let cell = if thumb.contains(&(y as usize)) {"█"} else {"│"};
+        imtui.put_str(pos + Point(0, y), cell, INACTIVE_PAIR);
+    }
+}
+
+/// The rows of a scrollbar `height` cells tall that the thumb takes, for
+/// `visible` of the `total` rows shown from `offset` on. None if they all
+/// fit.
+pub fn scrollbar_thumb(height: usize, offset: usize, total: usize, visible: usize) -> Range<usize> {
+    if total <= visible || height == 0 {
+        return 0..0;
+    }
+    let size = cmp::min(cmp::max(height * visible / total, 1), height);
+    let max_offset = total - visible;
+    let start = cmp::min(offset, max_offset) * (height - size) / max_offset;
+    start..start + size
+}
+
+/// scrollbar() taking a column of the layout, e.g. next to a list of
+/// `visible` rows in a LayoutType::Horz, for when the bar is not as tall
+/// as the list. Not focusable, it only shows where the list is.
+pub fn scrollbar_column(imtui: &mut ImTui, offset: usize, total: usize, visible: usize, height: i32) {
+    let pos = imtui.free_pos();
+    let height = cmp::max(height, 0);
+    let thumb = scrollbar_thumb(height as usize, offset, total, visible);
+    for y in 0..height {
+        let cell = if thumb.contains(&(y as usize)) {"█"} else {"│"};
+        imtui.put_str(pos + Point(0, y), cell, INACTIVE_PAIR);
+    }
+    imtui.add_size(Point(1, height));
+}
+
+/// log_view() with the state kept by ImTui.
+pub fn log_view_retained(imtui: &mut ImTui, lines: &[String], size: Point, id: Id) {
+    let mut state = imtui.take_state::<LogViewState>(id);
+    log_view(imtui, lines, size, &mut state, id);
+    imtui.put_state(id, state);
+}
+
+/// A vertical list of sections with `▌` marking the `*current` one. While
+/// focused Up and Down move the highlight and Enter makes the highlighted
+/// item current, returning true. The width is the one of the widest item
+/// so the sidebar does not jump around.
+pub fn nav_sidebar(imtui: &mut ImTui, items: &[&str], current: &mut usize, id: Id) -> bool {
+    let focused = imtui.is_hot(id);
+    let mut cursor = imtui.take_state::<Option<usize>>(id)
+        .filter(|_| focused)
+        .unwrap_or(*current);
+    let mut committed = false;
+
+    if focused && !imtui.any_active() {
+        match imtui.key() {
+            Some(Key::Up) if cursor == 0 => imtui.reject(),
+            Some(Key::Down) if cursor + 1 >= items.len() => imtui.reject(),
+            Some(Key::Up) => cursor -= 1,
+            Some(Key::Down) => cursor += 1,
+            Some(Key::Enter) if cursor < items.len() => {
+                *current = cursor;
+                committed = true;
+                imtui.push_action(Action::Activated(id));
+            }
+            _ => {}
+        }
+    }
+    cursor = cmp::min(cursor, items.len().saturating_sub(1));
+    imtui.put_state(id, Some(cursor));
+
+    imtui.register_id(id);
+    let pos = imtui.free_pos();
+
+    let width = items.iter().map(|x| text_width(x)).max().unwrap_or(0) + 2;
+    for (i, item) in items.iter().enumerate() {
+        let marker = if i == *current {"▌"} else {" "};
+        let text = format!("{} {}", marker, item);
+        let pair = if focused && i == cursor {
+            HOT_PAIR
+        } else if imtui.is_hovered(id) {
+            HOVERED_PAIR
+        } else {
+            INACTIVE_PAIR
+        };
+        imtui.put_str_fill(pos + Point(0, i as i32), &text, pair, width);
+    }
+
+    imtui.add_size(Point(width, items.len() as i32));
+
+    committed
+}
+
+/// A row of swatches of the base colors of the terminal with `•` on
+/// `*color`. While focused Left and Right move the brackets around the
+/// swatches and Enter picks the one in them, returning true.
+pub fn color_picker(imtui: &mut ImTui, color: &mut i16, id: Id) -> bool {
+    let colors = imtui.colors();
+    let focused = imtui.is_hot(id);
+    let mut cursor = imtui.take_state::<Option<i16>>(id)
+        .filter(|_| focused)
+        .unwrap_or(*color);
+    let mut changed = false;
+
+    if focused && !imtui.any_active() {
+        match imtui.key() {
+            Some(Key::Left) if cursor <= 0 => imtui.reject(),
+            Some(Key::Right) if cursor + 1 >= colors => imtui.reject(),
+            Some(Key::Left) => cursor -= 1,
+            Some(Key::Right) => cursor += 1,
+            Some(Key::Enter) if cursor != *color && (0..colors).contains(&cursor) => {
+                *color = cursor;
+                changed = true;
+                imtui.push_action(Action::Changed(id));
+            }
+            _ => {}
+        }
+    }
+    imtui.put_state(id, Some(cursor));
+
+    imtui.register_id(id);
+    let pos = imtui.free_pos();
+
+    for swatch in 0..colors {
+        let mark = if swatch == *color {"•"} else {" "};
+        let text = if focused && swatch == cursor {
+            format!("[{}]", mark)
+        } else {
+            format!(" {} ", mark)
+        };
+        imtui.put_str(pos + Point(swatch as i32 * 3, 0), &text, SWATCH_PAIR + swatch);
+    }
+
+    imtui.add_size(Point(colors as i32 * 3, 1));
+
+    changed
+}
+
+const SLIDER_WIDTH: i32 = 10;
+
+/// `*value` as a bar like `[####------] 40`. While focused Left and Right,
+/// or - and +, change it by `step`, stopping at the ends of `range`. A
+/// reversed range, e.g. one worked out at runtime, is taken the right way
+/// round. Returns true when it changed.
+pub fn slider(imtui: &mut ImTui, value: &mut i32, range: RangeInclusive<i32>, step: i32, id: Id) -> bool {
+    let range = cmp::min(*range.start(), *range.end())..=cmp::max(*range.start(), *range.end());
+    let old = *value;
+    let mut pair = INACTIVE_PAIR;
+
+    if imtui.is_hot(id) {
+        pair = HOT_PAIR;
+        if !imtui.any_active() {
+            let delta = match imtui.key() {
+                Some(Key::Left) | Some(Key::Char('-')) => -step,
+                Some(Key::Right) | Some(Key::Char('+')) => step,
+                _ => 0,
+            };
+            if delta != 0 {
+                let new = value.saturating_add(delta).clamp(*range.start(), *range.end());
+                if new == *value {
+                    imtui.reject();
+                }
+                *value = new;
+            }
+        }
+    } else if imtui.is_hovered(id) {
+        pair = HOVERED_PAIR;
+    }
+
+    let changed = *value != old;
+    if changed {
+        imtui.push_action(Action::Changed(id));
+    }
+
+    imtui.register_id(id);
+    let pos = imtui.free_pos();
+
+    let span = (*range.end() as i64 - *range.start() as i64).max(1);
+    let filled = ((*value as i64 - *range.start() as i64) * SLIDER_WIDTH as i64 / span).clamp(0, SLIDER_WIDTH as i64) as usize;
+    let bar = format!("[{}{}] {}", "#".repeat(filled), "-".repeat(SLIDER_WIDTH as usize - filled), value);
+    imtui.put_str(pos, &bar, pair);
+
+    imtui.add_size(measure_slider(&range));
+
+    changed
+}
+
+/// A vertical list showing `height` of the `items` from `*scroll` on, with
+/// the `*selected` one drawn with HOT_PAIR while focused. Up and Down move
+/// the selection, scrolling the list to keep it in view, and the mouse
+/// wheel scrolls it. Enter returns the selected index. The last column is
+/// a scrollbar() once there are more items than fit.
+pub fn list(imtui: &mut ImTui, items: &[&str], height: i32, scroll: &mut usize, selected: &mut usize, id: Id) -> Option<usize> {
+    let height = cmp::max(height, 0) as usize;
+    let focused = imtui.is_hot(id);
+    let mut picked = None;
+
+    if focused && !imtui.any_active() {
+        match imtui.key() {
+            Some(Key::Up) if *selected == 0 => imtui.reject(),
+            Some(Key::Down) if *selected + 1 >= items.len() => imtui.reject(),
+            Some(Key::Up) => *selected -= 1,
+            Some(Key::Down) => *selected += 1,
+            Some(Key::Enter) if *selected < items.len() => {
+                picked = Some(*selected);
+                imtui.push_action(Action::Activated(id));
+            }
+            _ => {}
+        }
+        if matches!(imtui.key(), Some(Key::Up) | Some(Key::Down)) {
+            if *selected < *scroll {
+                *scroll = *selected;
+            } else if *selected >= *scroll + height {
+                *scroll = *selected + 1 - height;
+            }
+        }
+    }
+    *selected = cmp::min(*selected, items.len().saturating_sub(1));
+
+    let wheel = imtui.wheel(id);
+    *scroll = (*scroll as i32 + wheel).max(0) as usize;
+    *scroll = cmp::min(*scroll, items.len().saturating_sub(height));
+
+    imtui.claim_up_down(id);
+    imtui.register_id(id);
+    let pos = imtui.free_pos();
+
+    let overflows = items.len() > height;
+    let width = items.iter().map(|x| text_width(x)).max().unwrap_or(0);
+    for row in 0..height {
+        let i = *scroll + row;
+        let pair = if focused && i == *selected {
+            HOT_PAIR
+        } else if imtui.is_hovered(id) {
+            HOVERED_PAIR
+        } else {
+            INACTIVE_PAIR
+        };
+        let item = items.get(i).cloned().unwrap_or("");
+        imtui.put_str_fill(pos + Point(0, row as i32), item, pair, width);
+    }
+    if overflows {
+        scrollbar(imtui, pos + Point(width, 0), height as i32, *scroll, items.len());
+    }
+
+    imtui.add_size(Point(width + overflows as i32, height as i32));
+
+    picked
+}
+
+/// A vertical list where Space toggles whether the highlighted item is in
+/// `selection`, which marks it with `✓`. The highlight is the cursor moved
+/// with Up and Down while focused, drawn with HOT_PAIR whether the item is
+/// selected or not, the `✓` alone telling the selected ones apart. Enter
+/// confirms the selection, returning true.
+pub fn multi_select_list(imtui: &mut ImTui, items: &[&str], selection: &mut HashSet<usize>, id: Id) -> bool {
+    let focused = imtui.is_hot(id);
+    let mut cursor = imtui.take_state::<usize>(id);
+    let mut confirmed = false;
+
+    if focused && !imtui.any_active() {
+        match imtui.key() {
+            Some(Key::Up) if cursor == 0 => imtui.reject(),
+            Some(Key::Down) if cursor + 1 >= items.len() => imtui.reject(),
+            Some(Key::Up) => cursor -= 1,
+            Some(Key::Down) => cursor += 1,
+            Some(Key::Char(' ')) if cursor < items.len() => {
+                if !selection.remove(&cursor) {
+                    selection.insert(cursor);
+                }
+                imtui.push_action(Action::Changed(id));
+            }
+            Some(Key::Enter) => {
+                confirmed = true;
+                imtui.push_action(Action::Activated(id));
+            }
+            _ => {}
+        }
+    }
+    cursor = cmp::min(cursor, items.len().saturating_sub(1));
+    imtui.put_state(id, cursor);
+
+    imtui.claim_up_down(id);
+    imtui.register_id(id);
+    let pos = imtui.free_pos();
+
+    let width = items.iter().map(|x| text_width(x)).max().unwrap_or(0) + 2;
+    for (i, item) in items.iter().enumerate() {
+        let mark = if selection.contains(&i) {"✓"} else {" "};
+        let text = format!("{} {}", mark, item);
+        let pair = if focused && i == cursor {
+            HOT_PAIR
+        } else if imtui.is_hovered(id) {
+            HOVERED_PAIR
+        } else {
+            INACTIVE_PAIR
+        };
+        imtui.put_str_fill(pos + Point(0, i as i32), &text, pair, width);
+    }
+
+    imtui.add_size(Point(width, items.len() as i32));
+
+    confirmed
+}
+
+/// A vertical list with a highlight moved with Up and Down while focused.
+/// Shift+Up and Shift+Down move the highlighted item itself: the widget
+/// does not own the items, so it returns the (from, to) indices for the
+/// caller to swap. The highlight goes along with the item.
+pub fn reorderable_list(imtui: &mut ImTui, items: &[&str], id: Id) -> Option<(usize, usize)> {
+    let focused = imtui.is_hot(id);
+    let mut cursor = imtui.take_state::<usize>(id);
+    let mut moved = None;
+
+    if focused && !imtui.any_active() {
+        match imtui.key() {
+            Some(Key::Up) | Some(Key::ShiftUp) if cursor == 0 => imtui.reject(),
+            Some(Key::Down) | Some(Key::ShiftDown) if cursor + 1 >= items.len() => imtui.reject(),
+            Some(Key::Up) => cursor -= 1,
+            Some(Key::Down) => cursor += 1,
+            Some(Key::ShiftUp) => moved = Some((cursor, cursor - 1)),
+            Some(Key::ShiftDown) => moved = Some((cursor, cursor + 1)),
+            _ => {}
+        }
+    }
+    if let Some((from, to)) = moved {
+        cursor = to;
+        imtui.push_action(Action::Moved(id, from, to));
+    }
+    cursor = cmp::min(cursor, items.len().saturating_sub(1));
+    imtui.put_state(id, cursor);
+
+    imtui.claim_up_down(id);
+    imtui.register_id(id);
+    let pos = imtui.free_pos();
+
+    let width = items.iter().map(|x| text_width(x)).max().unwrap_or(0);
+    for (i, item) in items.iter().enumerate() {
+        let pair = if focused && i == cursor {
+            HOT_PAIR
+        } else if imtui.is_hovered(id) {
+            HOVERED_PAIR
+        } else {
+            INACTIVE_PAIR
+        };
+        imtui.put_str_fill(pos + Point(0, i as i32), item, pair, width);
+    }
+
+    imtui.add_size(Point(width, items.len() as i32));
+
+    moved
+}
+
+const EDIT_FIELD_SIZE: Point = Point(20, 1);
+const EDIT_FIELD_MIN_WIDTH: i32 = 5;
+
+/// Returns true only on the frames where the content of the buffer was
+/// actually modified. Focus changes and (de)activation do not count.
+pub fn edit_field(imtui: &mut ImTui, buffer: &mut String, cursor: &mut usize, id: Id) -> bool {
+    edit_field_with_pair(imtui, buffer, cursor, INACTIVE_PAIR, EDIT_FIELD_SIZE.0, id)
+}
+
+/// edit_field() keeping the cursor in ImTui, under a child of `id`, for
+/// the callers that do not need it.
+pub fn text_input(imtui: &mut ImTui, buffer: &mut String, id: Id) -> bool {
+    let cursor_id = id.child(1);
+    let mut cursor = imtui.take_state::<usize>(cursor_id);
+    let changed = edit_field(imtui, buffer, &mut cursor, id);
+    imtui.put_state(cursor_id, cursor);
+    changed
+}
+
+/// edit_field() returning true on the frame Enter finished the editing
+/// instead, e.g. for submitting a form from its last field. Leaving the
+/// field with Esc does not count. Enter also pushes Action::Activated in
+/// any edit_field().
+pub fn edit_field_submitted(imtui: &mut ImTui, buffer: &mut String, cursor: &mut usize, id: Id) -> bool {
+    let entered = imtui.is_active(id) && imtui.key() == Some(Key::Enter);
+    edit_field(imtui, buffer, cursor, id);
+    entered && !imtui.is_active(id)
+}
+
+/// edit_field() holding at most `max_len` characters. The characters typed
+/// into a full field are rejected, and of a paste only as much is kept as
+/// fits.
+pub fn edit_field_max_len(imtui: &mut ImTui, buffer: &mut String, cursor: &mut usize, max_len: usize, id: Id) -> bool {
+    if imtui.is_active(id) && buffer.chars().count() >= max_len {
+        if let Some(Key::Char(_)) = imtui.key() {
+            imtui.reject();
+            imtui.consume_key();
+        }
+    }
+
+    let len = buffer.chars().count();
+    let changed = edit_field(imtui, buffer, cursor, id);
+
+    // Only a paste grows the field by more than a character. What went in
+    // is right before the cursor.
+    let excess = buffer.chars().count().saturating_sub(cmp::max(max_len, len));
+    if excess > 0 {
+        imtui.reject();
+        let start = cursor.saturating_sub(excess);
+        buffer.replace_range(byte_offset(buffer, start)..byte_offset(buffer, *cursor), "");
+        *cursor = start;
+    }
+
+    changed
+}
+
+/// edit_field() taking `width` columns instead of EDIT_FIELD_SIZE.0. A
+/// field with no width takes no room and draws nothing, but is still
+/// there to be focused and edited.
+pub fn edit_field_sized(imtui: &mut ImTui, buffer: &mut String, cursor: &mut usize, width: i32, id: Id) -> bool {
+    edit_field_with_pair(imtui, buffer, cursor, INACTIVE_PAIR, width, id)
+}
+
+/// edit_field() taking all the width left in the current clipping region,
+/// e.g. the rest of a form row. It is never narrower than
+/// EDIT_FIELD_MIN_WIDTH though: without enough room it is clipped like any
+/// other widget rather than moved to the next line.
+pub fn edit_field_fill(imtui: &mut ImTui, buffer: &mut String, cursor: &mut usize, id: Id) -> bool {
+    let width = cmp::max(imtui.remaining_width(), EDIT_FIELD_MIN_WIDTH);
+    edit_field_with_pair(imtui, buffer, cursor, INACTIVE_PAIR, width, id)
+}
+
+/// edit_field() where Enter starts a new line instead of finishing the
+/// editing, the field growing by a row per line. Esc finishes it then, the
+/// same way it leaves any active widget. Tab is not handled in either kind
+/// of field, it just does nothing while editing.
+pub fn edit_field_multiline(imtui: &mut ImTui, buffer: &mut String, cursor: &mut usize, id: Id) -> bool {
+    edit_field_with_kind(imtui, buffer, cursor, INACTIVE_PAIR, EDIT_FIELD_SIZE.0, FieldKind::Multiline, id)
+}
+
+/// edit_field_multiline() taking `size` no matter how many lines there
+/// are, scrolling up and down to keep the caret in it too.
+pub fn text_area(imtui: &mut ImTui, buffer: &mut String, cursor: &mut usize, size: Point, id: Id) -> bool {
+    edit_field_with_kind(imtui, buffer, cursor, INACTIVE_PAIR, size.0, FieldKind::Area(size.1), id)
+}
+
+/// edit_field() that draws every character of the text as `*`, for the
+/// things that shouldn't be echoed to the screen. The buffer still holds
+/// the real text, and Ctrl+C doesn't copy it.
+pub fn password_field(imtui: &mut ImTui, buffer: &mut String, cursor: &mut usize, id: Id) -> bool {
+    edit_field_with_kind(imtui, buffer, cursor, INACTIVE_PAIR, EDIT_FIELD_SIZE.0, FieldKind::Password, id)
+}
+
+/// The kinds of edit fields, differing in what Enter does and how the text
+/// is drawn.
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub enum FieldKind {
+    Line,
+    Multiline,
+    /// Multiline with that many rows.
+    Area(i32),
+    Password,
+}
+
+impl FieldKind {
+    pub fn multiline(self) -> bool {
+        matches!(self, FieldKind::Multiline | FieldKind::Area(_))
+    }
+}
+
+/// edit_field() drawn with `inactive_pair` whenever it is not hot and
+/// taking `width` columns.
+pub fn edit_field_with_pair(imtui: &mut ImTui, buffer: &mut String, cursor: &mut usize, inactive_pair: i16, width: i32, id: Id) -> bool {
+    edit_field_with_kind(imtui, buffer, cursor, inactive_pair, width, FieldKind::Line, id)
+}
+
+// The byte offset of the `index`-th character of `text`, or the end of it.
+fn byte_offset(text: &str, index: usize) -> usize {
+    text.char_indices().nth(index).map(|(i, _)| i).unwrap_or(text.len())
+}
+
+// Inserts `text` at the cursor, leaving out the newlines unless the field
+// is a multiline one, and the other control characters. Returns whether
+// anything was inserted.
+fn insert_pasted(buffer: &mut String, cursor: &mut usize, text: &str, kind: FieldKind) -> bool {
+    let text: String = text.chars()
+        .filter(|x| !x.is_control() || (*x == '\n' && kind.multiline()))
+        .collect();
+    buffer.insert_str(byte_offset(buffer, *cursor), &text);
+    *cursor += text.chars().count();
+    !text.is_empty()
+}
+
+/// `*cursor` is the position of the caret in characters, where the typed
+/// characters are inserted. Starting editing moves it to the end of the
+/// buffer, then Left and Right move it by a character and Home and End to
+/// either end. Backspace removes the character before it and Delete the
+/// one after it, if there is one. In the multiline fields Up and Down move
+/// it to the same column of the line above and below, or to its end if it
+/// is shorter.
+pub fn edit_field_with_kind(imtui: &mut ImTui, buffer: &mut String, cursor: &mut usize, inactive_pair: i16, width: i32, kind: FieldKind, id: Id) -> bool {
+    let mut changed = false;
+    let mut pair = inactive_pair;
+
+    if imtui.is_active(id) {
+        let len = buffer.chars().count();
+        *cursor = cmp::min(*cursor, len);
+        if let Some(key) = imtui.key() {
+            changed = match key {
+                Key::Enter if kind.multiline() => {
+                    buffer.insert(byte_offset(buffer, *cursor), '\n');
+                    *cursor += 1;
+                    true
+                }
+                Key::Enter => {
+                    imtui.deactivate();
+                    imtui.push_action(Action::Activated(id));
+                    false
+                }
+                Key::Char(x) => {
+                    buffer.insert(byte_offset(buffer, *cursor), x);
+                    *cursor += 1;
+                    true
+                }
+                Key::Backspace if *cursor > 0 => {
+                    *cursor -= 1;
+                    buffer.remove(byte_offset(buffer, *cursor));
+                    true
+                }
+                Key::Delete if *cursor < len => {
+                    buffer.remove(byte_offset(buffer, *cursor));
+                    true
+                }
+                Key::Left if *cursor > 0 => {
+                    *cursor -= 1;
+                    false
+                }
+                Key::Right if *cursor < len => {
+                    *cursor += 1;
+                    false
+                }
+                Key::Backspace | Key::Delete | Key::Left | Key::Right => {
+                    imtui.reject();
+                    false
+                }
+                Key::Up | Key::Down if kind.multiline() => {
+                    let before = &buffer[..byte_offset(buffer, *cursor)];
+                    let line = before.matches('\n').count();
+                    let column = before.rsplit('\n').next().unwrap_or("").chars().count();
+                    let lines: Vec<usize> = buffer.split('\n').map(|x| x.chars().count()).collect();
+                    let target = match key {
+                        Key::Up => line.checked_sub(1),
+                        _ => Some(line + 1).filter(|x| *x < lines.len()),
+                    };
+                    match target {
+                        Some(target) => {
+                            let start: usize = lines[..target].iter().map(|x| x + 1).sum();
+                            *cursor = start + cmp::min(column, lines[target]);
+                        }
+                        None => imtui.reject(),
+                    }
+                    false
+                }
+                Key::Home => {
+                    *cursor = 0;
+                    false
+                }
+                Key::End => {
+                    *cursor = len;
+                    false
+                }
+                Key::Paste => insert_pasted(buffer, cursor, imtui.pasted(), kind),
+                #[cfg(feature = "clipboard")]
+                Key::Ctrl('v') => insert_pasted(buffer, cursor, &clipboard::paste(), kind),
+                #[cfg(feature = "clipboard")]
+                Key::Ctrl('c') if kind != FieldKind::Password => {
+                    clipboard::copy(buffer);
+                    false
+                }
+                _ => false,
+            };
+            if changed {
+                imtui.push_action(Action::Changed(id));
+            }
+        }
+    } else if imtui.is_hot(id) {
+        pair = HOT_PAIR;
+        if !imtui.any_active() && imtui.key() == Some(Key::Enter) {
+            *cursor = buffer.chars().count();
+            imtui.set_text_input_active(id);
+            pair = inactive_pair;
+        }
+    } else if imtui.is_hovered(id) {
+        pair = HOVERED_PAIR;
+    }
+
+    imtui.register_id(id);
+    let pos = imtui.free_pos();
+
+    let lines = buffer.split('\n').count() as i32;
+    let rows = match kind {
+        FieldKind::Area(rows) => cmp::max(rows, 0),
+        _ => EDIT_FIELD_SIZE.1 * lines,
+    };
+    let size = Point(cmp::max(width, 0), rows);
+    // The preedit text goes where it is going to be inserted.
+    let mut preedit = if imtui.is_active(id) {imtui.preedit().to_string()} else {String::new()};
+    if kind == FieldKind::Password {
+        preedit = preedit.chars().map(|_| '*').collect();
+    }
+    // A password is masked before anything is measured or clipped, so it
+    // takes a column per character whatever the characters are.
+    let text: Cow<str> = match kind {
+        FieldKind::Password => buffer.chars().map(|x| if x == '\n' {x} else {'*'}).collect(),
+        _ => Cow::Borrowed(buffer.as_str()),
+    };
+    let before = &text[..byte_offset(&text, *cursor)];
+    let caret_line = before.matches('\n').count() as i32;
+    let caret_column = text_width(before.rsplit('\n').next().unwrap_or("")) as usize;
+
+    // While editing the text scrolls sideways just enough to keep the
+    // caret in the field, and up and down in a text_area(). The scroll is
+    // kept under a child id, the id itself may have the state of a widget
+    // built on top, like int_field().
+    let scroll_id = id.child(0);
+    let (mut scroll, mut scroll_rows) = imtui.take_state::<(usize, usize)>(scroll_id);
+    if imtui.is_active(id) {
+        let caret_end = caret_column + text_width(&preedit) as usize;
+        scroll = cmp::min(scroll, caret_column);
+        scroll = cmp::max(scroll, (caret_end + 1).saturating_sub(size.0 as usize));
+        scroll_rows = cmp::min(scroll_rows, caret_line as usize);
+        scroll_rows = cmp::max(scroll_rows, (caret_line + 1 - size.1).max(0) as usize);
+    } else {
+        scroll = 0;
+        scroll_rows = 0;
+    }
+    imtui.put_state(scroll_id, (scroll, scroll_rows));
+
+    let mut shown = text.split('\n').skip(scroll_rows);
+    for y in 0..size.1 {
+        let line = shown.next().unwrap_or("");
+        let line_pos = pos + Point(0, y);
+        let line = clip_columns(line, scroll as i32, scroll as i32 + size.0);
+        imtui.put_str_fill(line_pos, line, pair, size.0);
+    }
+
+    let preedit_pos = pos + Point(cmp::min(caret_column.saturating_sub(scroll) as i32, size.0 - 1), caret_line - scroll_rows as i32);
+    if imtui.is_active(id) && size.0 > 0 {
+        let caret = preedit_pos + Point(text_width(&preedit), 0);
+        let caret = Point(cmp::min(caret.0, pos.0 + size.0 - 1), caret.1);
+        if imtui.clip_rect().contains(caret) {
+            imtui.set_caret(caret);
+        }
+    }
+
+    if !preedit.is_empty() {
+        imtui.push_clip(Rect::new(pos, size));
+        imtui.put_str_attr(preedit_pos, &preedit, pair, A_UNDERLINE());
+        imtui.pop_clip();
+    }
+
+    imtui.add_size(size);
+
+    changed
+}
+
+/// An edit_field() preceded by the chips committed so far, drawn like
+/// `[tag ×]`. While it is active Enter commits the text in the buffer as a
+/// new chip instead of finishing the editing, which an Enter on the empty
+/// buffer still does, and Backspace on the empty buffer removes the last
+/// chip. Returns true whenever the chips or the buffer changed.
+pub fn tag_input(imtui: &mut ImTui, chips: &mut Vec<String>, buffer: &mut String, cursor: &mut usize, id: Id) -> bool {
+    let mut changed = false;
+
+    if imtui.is_active(id) {
+        match imtui.key() {
+            Some(Key::Enter) if !buffer.is_empty() => {
+                chips.push(std::mem::take(buffer));
+                *cursor = 0;
+                changed = true;
+            }
+            Some(Key::Backspace) if buffer.is_empty() => {
+                if chips.pop().is_some() {
+                    changed = true;
+                } else {
+                    imtui.reject();
+                }
+            }
+            _ => {}
+        }
+        if changed {
+            imtui.consume_key();
+            imtui.push_action(Action::Changed(id));
+        }
+    }
+
+    imtui.begin_layout(LayoutType::Horz, 1);
+    for chip in chips.iter() {
+        label(imtui, &format!("[{} ×]", chip));
+    }
+    changed |= edit_field(imtui, buffer, cursor, id);
+    imtui.end_layout();
+
+    changed
+}
+
+/// A month calendar. Enter on the focused picker starts navigating: the
+/// arrows move by a day or a week, PageUp/PageDown by a month, Enter
+/// makes the highlighted day the `*date` and returns true, Esc cancels.
+/// The picked day is drawn reversed and today underlined.
+#[cfg(feature = "chrono")]
+pub fn date_picker(imtui: &mut ImTui, date: &mut NaiveDate, id: Id) -> bool {
+    const SIZE: Point = Point(20, 8);
+
+    let mut cursor = imtui.take_state::<Option<NaiveDate>>(id).unwrap_or(*date);
+    let mut committed = false;
+
+    let mut pair = INACTIVE_PAIR;
+    if imtui.is_active(id) {
+        pair = ACTIVE_PAIR;
+        let moved = match imtui.key() {
+            Some(Key::Left) => cursor.pred_opt(),
+            Some(Key::Right) => cursor.succ_opt(),
+            Some(Key::Up) => cursor.checked_sub_days(Days::new(7)),
+            Some(Key::Down) => cursor.checked_add_days(Days::new(7)),
+            Some(Key::PageUp) => cursor.checked_sub_months(Months::new(1)),
+            Some(Key::PageDown) => cursor.checked_add_months(Months::new(1)),
+            _ => None,
+        };
+        cursor = moved.unwrap_or(cursor);
+
+        if imtui.key() == Some(Key::Enter) {
+            *date = cursor;
+            committed = true;
+            imtui.deactivate();
+            imtui.push_action(Action::Changed(id));
+        }
+    } else if imtui.is_hot(id) {
+        pair = HOT_PAIR;
+        if !imtui.any_active() && imtui.key() == Some(Key::Enter) {
+            imtui.activate(id);
+            pair = ACTIVE_PAIR;
+        }
+    } else if imtui.is_hovered(id) {
+        pair = HOVERED_PAIR;
+    }
+
+    if !imtui.is_active(id) {
+        cursor = *date;
+    }
+    imtui.put_state(id, Some(cursor));
+
+    imtui.register_id(id);
+    let pos = imtui.free_pos();
+
+    let title = cursor.format("%B %Y").to_string();
+    let title = format!("{:^width$}", title, width = SIZE.0 as usize);
+    imtui.put_str(pos, &title, pair);
+    imtui.put_str(pos + Point(0, 1), "Mo Tu We Th Fr Sa Su", INACTIVE_PAIR);
+
+    let today = Local::now().date_naive();
+    let first = cursor.with_day(1).unwrap();
+    let offset = first.weekday().num_days_from_monday() as i32;
+    for day in first.iter_days().take_while(|x| x.month() == first.month()) {
+        let cell = offset + day.day() as i32 - 1;
+        let cell_pos = pos + Point(cell % 7 * 3, 2 + cell / 7);
+
+        let mut attr = A_NORMAL();
+        if day == *date {
+            attr |= A_REVERSE();
+        }
+        if day == today {
+            attr |= A_UNDERLINE();
+        }
+        let day_pair = if imtui.is_active(id) && day == cursor {ACTIVE_PAIR} else {INACTIVE_PAIR};
+        imtui.put_str_attr(cell_pos, &format!("{:2}", day.day()), day_pair, attr);
+    }
+
+    imtui.add_size(SIZE);
+
+    committed
+}
+
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub enum ParseErr {
+    Invalid,
+    OutOfRange,
+}
+
+#[derive(Default)]
+struct IntFieldState {
+    text: String,
+    cursor: usize,
+    // The value `text` was last made from or parsed into.
+    synced: Option<i64>,
+}
+
+/// An edit_field() holding an integer in `range`. The text being typed is
+/// kept by ImTui, so it stays visible even when it is not a valid number
+/// and the field is then drawn with ERROR_PAIR. `*value` is only updated
+/// with valid numbers, an empty text leaves it as it was. Only the digits
+/// and a leading `-` can be typed, the other characters are rejected.
+/// Still, a paste may bring them in. A digit typed into a lone `0`
+/// replaces it. A number too big for an i64 is out of range, whatever
+/// `range` is. The returned error can be used to block submission of the
+/// form.
+pub fn int_field(imtui: &mut ImTui, value: &mut i64, range: RangeInclusive<i64>, id: Id) -> Result<(), ParseErr> {
+    let mut state = imtui.take_state::<IntFieldState>(id);
+
+    let parse = |text: &str| match text.trim().parse::<i64>() {
+        Ok(x) if range.contains(&x) => Ok(x),
+        Ok(_) => Err(ParseErr::OutOfRange),
+        Err(e) if matches!(e.kind(), IntErrorKind::PosOverflow | IntErrorKind::NegOverflow) => Err(ParseErr::OutOfRange),
+        Err(_) => Err(ParseErr::Invalid),
+    };
+
+    // Pick up the changes made to the value outside of the field, unless
+    // the user is in the middle of editing it.
+    if !imtui.is_active(id) && state.synced != Some(*value) {
+        state.text = value.to_string();
+        state.cursor = state.text.chars().count();
+        state.synced = Some(*value);
+    }
+
+    if imtui.is_active(id) {
+        if let Some(Key::Char(x)) = imtui.key() {
+            let sign = x == '-' && state.cursor == 0 && !state.text.starts_with('-');
+            if !x.is_ascii_digit() && !sign {
+                imtui.reject();
+                imtui.consume_key();
+            } else if x.is_ascii_digit() && state.text == "0" {
+                state.text.clear();
+                state.cursor = 0;
+            }
+        }
+    }
+
+    let pair = if parse(&state.text).is_ok() {INACTIVE_PAIR} else {ERROR_PAIR};
+    if edit_field_with_pair(imtui, &mut state.text, &mut state.cursor, pair, EDIT_FIELD_SIZE.0, id) {
+        if let Ok(x) = parse(&state.text) {
+            *value = x;
+            state.synced = Some(x);
+        }
+    }
+    let result = parse(&state.text).map(|_| ());
+
+    imtui.put_state(id, state);
+
+    result
+}
+
+pub const INACTIVE_PAIR: i16 = 1;
+pub const HOT_PAIR: i16 = 2;
+pub const ACTIVE_PAIR: i16 = 3;
+pub const ERROR_PAIR: i16 = 4;
+pub const HOVERED_PAIR: i16 = 5;
+pub const STATUS_PAIR: i16 = 6;
+/// The button-like widgets in a begin_disabled() scope.
+pub const DISABLED_PAIR: i16 = 7;
+/// SWATCH_PAIR + color has `color` as the background, for the
+/// color_picker(). These do not change with the theme.
+pub const SWATCH_PAIR: i16 = 16;
+
+fn init_swatch_pairs(colors: i16) {
+    for color in 0..colors {
+        init_pair(SWATCH_PAIR + color, swatch_fg(color), color);
+    }
+}
+
+fn swatch_fg(color: i16) -> i16 {
+    if color == COLOR_BLACK {COLOR_WHITE} else {COLOR_BLACK}
+}
+
+/// (foreground, background) colors of each of the pairs.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct Theme {
+    pub inactive: (i16, i16),
+    pub hot: (i16, i16),
+    pub active: (i16, i16),
+    pub error: (i16, i16),
+    pub hovered: (i16, i16),
+    pub status: (i16, i16),
+    pub disabled: (i16, i16),
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            inactive: (COLOR_WHITE, COLOR_BLACK),
+            hot: (COLOR_BLACK, COLOR_WHITE),
+            active: (COLOR_BLACK, COLOR_RED),
+            error: (COLOR_RED, COLOR_BLACK),
+            hovered: (COLOR_BLACK, COLOR_CYAN),
+            status: (COLOR_WHITE, COLOR_BLUE),
+            disabled: (COLOR_WHITE, COLOR_BLACK),
+        }
+    }
+}
+
+impl Theme {
+    pub fn high_contrast() -> Self {
+        Self {
+            inactive: (COLOR_YELLOW, COLOR_BLACK),
+            hot: (COLOR_BLACK, COLOR_YELLOW),
+            active: (COLOR_BLACK, COLOR_GREEN),
+            error: (COLOR_WHITE, COLOR_RED),
+            hovered: (COLOR_BLACK, COLOR_CYAN),
+            status: (COLOR_BLACK, COLOR_WHITE),
+            disabled: (COLOR_WHITE, COLOR_BLACK),
+        }
+    }
+
+    // The presets below are in the 256 color palette, apply() brings them
+    // down to the base colors on the terminals without it.
+
+    pub fn solarized_dark() -> Self {
+        Self {
+            inactive: (244, 234),
+            hot: (234, 33),
+            active: (234, 136),
+            error: (160, 234),
+            hovered: (234, 162),
+            status: (234, 37),
+            disabled: (240, 234),
+        }
+    }
+
+    pub fn monokai() -> Self {
+        Self {
+            inactive: (231, 235),
+            hot: (235, 148),
+            active: (235, 197),
+            error: (197, 235),
+            hovered: (235, 81),
+            status: (231, 238),
+            disabled: (242, 235),
+        }
+    }
+
+    pub fn default_light() -> Self {
+        Self {
+            inactive: (16, 231),
+            hot: (231, 25),
+            active: (231, 160),
+            error: (160, 231),
+            hovered: (16, 45),
+            status: (231, 240),
+            disabled: (248, 231),
+        }
+    }
+
+    fn colors(&self, pair: i16) -> (i16, i16) {
+        match pair {
+            HOT_PAIR => self.hot,
+            ACTIVE_PAIR => self.active,
+            ERROR_PAIR => self.error,
+            HOVERED_PAIR => self.hovered,
+            STATUS_PAIR => self.status,
+            DISABLED_PAIR => self.disabled,
+            _ if (SWATCH_PAIR..SWATCH_PAIR + 16).contains(&pair) => (swatch_fg(pair - SWATCH_PAIR), pair - SWATCH_PAIR),
+            _ => self.inactive,
+        }
+    }
+
+    fn apply(&self) {
+        let colors = COLORS();
+        let fit = |color: i16| if (color as i32) < colors {color} else {base_color(color)};
+        for pair in [INACTIVE_PAIR, HOT_PAIR, ACTIVE_PAIR, ERROR_PAIR, HOVERED_PAIR, STATUS_PAIR, DISABLED_PAIR] {
+            let (fg, bg) = self.colors(pair);
+            init_pair(pair, fit(fg), fit(bg));
+        }
+    }
+}
+
+// The closest of the 8 base colors to the `color` of the xterm 256 color
+// palette, going by how xterm draws them.
+fn base_color(color: i16) -> i16 {
+    const BASE: [(i32, i32, i32); 8] = [
+        (0, 0, 0), (205, 0, 0), (0, 205, 0), (205, 205, 0),
+        (0, 0, 238), (205, 0, 205), (0, 205, 205), (229, 229, 229),
+    ];
+    const CUBE: [i32; 6] = [0, 95, 135, 175, 215, 255];
+
+    let color = color as i32;
+    let (r, g, b) = match color {
+        0..=7 => return color as i16,
+        8..=15 => return (color - 8) as i16,
+        16..=231 => {
+            let i = color - 16;
+            (CUBE[(i / 36) as usize], CUBE[(i / 6 % 6) as usize], CUBE[(i % 6) as usize])
+        }
+        232..=255 => {
+            let level = 8 + (color - 232) * 10;
+            (level, level, level)
+        }
+        _ => return COLOR_WHITE,
+    };
+    // Otherwise the grays come out tinted with whichever color is nearest.
+    if r == g && g == b {
+        return if r < 100 {COLOR_BLACK} else {COLOR_WHITE};
+    }
+
+    let distance = |(br, bg, bb): (i32, i32, i32)| (r - br).pow(2) + (g - bg).pow(2) + (b - bb).pow(2);
+    (0..BASE.len()).min_by_key(|i| distance(BASE[*i])).unwrap() as i16
+}
+
+// Access to the system clipboard for the edit fields: Ctrl+V pastes into
+// the active one, Ctrl+C copies its content. The clipboard being
+// unavailable (no display server, for instance) is not an error, pasting
+// just inserts nothing.
+#[cfg(feature = "clipboard")]
+mod clipboard {
+    use copypasta::{ClipboardContext, ClipboardProvider};
+
+    pub fn paste() -> String {
+        ClipboardContext::new()
+            .and_then(|mut ctx| ctx.get_contents())
+            .unwrap_or_default()
+    }
+
+    pub fn copy(text: &str) {
+        if let Ok(mut ctx) = ClipboardContext::new() {
+            let _ = ctx.set_contents(text.to_owned());
+        }
+    }
+}
+
+/// Hands out the ids one after another, all of them non-negative. The
+/// widgets made in a loop should use base.child(index) of a `base` from
+/// here instead, which stays the same every frame no matter how many
+/// widgets came before. Those are negative, so the two never collide.
+#[derive(Default)]
+pub struct GenId {
+    count: i32
+}
+
+impl GenId {
+    pub fn new() -> Self {
+        Self {count: 0}
+    }
+
+    pub fn next_id(&mut self) -> Id {
+        let id = self.count;
+        self.count = self.count.checked_add(1).expect("ran out of ids");
+        Id(id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Feeds `key`, if any, and runs a whole frame of `f` on `imtui`.
+    fn frame<R>(imtui: &mut ImTui, key: Option<i32>, f: impl FnOnce(&mut ImTui) -> R) -> R {
+        if let Some(key) = key {
+            imtui.feed_key(key);
+        }
+        imtui.begin(Point(0, 0));
+        let result = f(imtui);
+        imtui.end().unwrap();
+        imtui.present();
+        result
+    }
+
+    #[test]
+    fn horz_layout_pads_only_between_children() {
+        let mut imtui = ImTui::init().build_headless(Point(40, 5));
+        let width = frame(&mut imtui, None, |imtui| {
+            imtui.begin_layout(LayoutType::Horz, 1);
+            button(imtui, "b1", Id(1));
+            button(imtui, "b2", Id(2));
+            imtui.end_layout();
+            imtui.layouts.last().unwrap().size.0
+        });
+        let glyphs = Glyphs::ascii();
+        assert_eq!(width, measure_button(&glyphs, "b1").0 + 1 + measure_button(&glyphs, "b2").0);
+    }
+
+    // A button before a row of three that can be hidden, and one after.
+    fn form_with_row(imtui: &mut ImTui, row: bool) -> Option<Id> {
+        button(imtui, "First", Id(1));
+        if row {
+            imtui.begin_layout(LayoutType::Horz, 1);
+            button(imtui, "Submit", Id(2));
+            button(imtui, "Clear", Id(3));
+            button(imtui, "Quit", Id(4));
+            imtui.end_layout();
+        }
+        button(imtui, "Last", Id(5));
+        imtui.hot()
+    }
+
+    #[test]
+    fn hidden_focused_widget_hands_focus_to_next_one() {
+        let mut imtui = ImTui::init().build_headless(Point(40, 5));
+        frame(&mut imtui, None, |imtui| form_with_row(imtui, true));
+        frame(&mut imtui, Some('s' as i32), |imtui| form_with_row(imtui, true));
+        let hot = frame(&mut imtui, Some('s' as i32), |imtui| form_with_row(imtui, true));
+        assert_eq!(hot, Some(Id(3)));
+
+        frame(&mut imtui, None, |imtui| form_with_row(imtui, false));
+        let hot = frame(&mut imtui, None, |imtui| form_with_row(imtui, false));
+        assert_eq!(hot, Some(Id(5)));
+    }
+
+    #[test]
+    fn focus_stays_on_its_widget_when_a_section_before_is_hidden() {
+        let mut imtui = ImTui::init().build_headless(Point(40, 5));
+        frame(&mut imtui, None, |imtui| form_with_row(imtui, true));
+        let hot = frame(&mut imtui, Some(KEY_END), |imtui| form_with_row(imtui, true));
+        assert_eq!(hot, Some(Id(5)));
+
+        for _ in 0..2 {
+            let hot = frame(&mut imtui, None, |imtui| form_with_row(imtui, false));
+            assert_eq!(hot, Some(Id(5)));
+        }
+        assert_eq!(imtui.focus(), 1);
+    }
+
+    #[test]
+    fn poll_timeout_is_no_key() {
+        let mut imtui = ImTui::init().build_headless(Point(40, 5));
+        imtui.feed_key(ERR);
+        assert_eq!(imtui.key, None);
+        let key = frame(&mut imtui, None, |imtui| imtui.key());
+        assert_eq!(key, None);
+    }
+
+    // Runs `widget` for a frame without a key, so it gets the focus, one
+    // with Enter to activate it and then one per key of `keys`.
+    fn feed_frames(imtui: &mut ImTui, keys: &[i32], mut widget: impl FnMut(&mut ImTui)) {
+        frame(imtui, None, &mut widget);
+        frame(imtui, Some('\n' as i32), &mut widget);
+        for key in keys {
+            frame(imtui, Some(*key), &mut widget);
+        }
+    }
+
+    #[test]
+    fn edit_field_decodes_utf8_bytes() {
+        let mut imtui = ImTui::init().build_headless(Point(40, 5));
+        let (mut buffer, mut cursor) = (String::new(), 0);
+        let keys: Vec<i32> = "café".bytes().map(i32::from).collect();
+        feed_frames(&mut imtui, &keys, |imtui| {
+            edit_field(imtui, &mut buffer, &mut cursor, Id(1));
+        });
+        assert_eq!(buffer, "café");
+        assert_eq!(buffer.chars().count(), 4);
+        assert_eq!(cursor, 4);
+    }
+
+    #[test]
+    fn edit_field_backspace_and_delete() {
+        let mut imtui = ImTui::init().build_headless(Point(40, 5));
+        let (mut buffer, mut cursor) = (String::new(), 0);
+        let keys = ['a' as i32, 'b' as i32, 'c' as i32, 127, KEY_BACKSPACE, KEY_DC];
+        feed_frames(&mut imtui, &keys, |imtui| {
+            edit_field(imtui, &mut buffer, &mut cursor, Id(1));
+        });
+        assert_eq!(buffer, "a");
+        assert_eq!(cursor, 1);
+
+        // Neither of them goes past the ends of the text.
+        for key in [KEY_HOME, KEY_BACKSPACE, KEY_END, KEY_DC] {
+            frame(&mut imtui, Some(key), |imtui| edit_field(imtui, &mut buffer, &mut cursor, Id(1)));
+        }
+        assert_eq!(buffer, "a");
+        assert_eq!(cursor, 1);
+    }
+
+    #[test]
+    fn edit_field_scrolls_to_the_cursor() {
+        let mut imtui = ImTui::init().build_headless(Point(40, 5));
+        let (mut buffer, mut cursor) = (String::new(), 0);
+        let text = "abcdefghijklmnopqrstuvwxy";
+        let keys: Vec<i32> = text.bytes().map(i32::from).collect();
+        feed_frames(&mut imtui, &keys, |imtui| {
+            edit_field(imtui, &mut buffer, &mut cursor, Id(1));
+        });
+        let row = imtui.frame_text(false).lines().next().unwrap().to_string();
+        assert!(row.ends_with('y') && !row.contains('a'), "{:?}", row);
+        assert!(text_width(&row) <= EDIT_FIELD_SIZE.0);
+
+        frame(&mut imtui, Some(KEY_HOME), |imtui| edit_field(imtui, &mut buffer, &mut cursor, Id(1)));
+        let row = imtui.frame_text(false).lines().next().unwrap().to_string();
+        assert!(row.starts_with("abc"), "{:?}", row);
+    }
+
+    #[test]
+    fn radio_group_selects_the_activated_option() {
+        let mut imtui = ImTui::init().build_headless(Point(40, 5));
+        let items = ["One", "Two", "Three"];
+        let mut selected = 0;
+        let mut changes = Vec::new();
+        for key in [None, Some('s' as i32), Some('s' as i32), Some('\n' as i32), None, None] {
+            changes.push(frame(&mut imtui, key, |imtui| radio_group(imtui, &items, &mut selected, Id(1))));
+        }
+        assert_eq!(selected, 2);
+        assert_eq!(changes, [false, false, false, false, true, false]);
+        assert_eq!(imtui.ids().len(), 3);
+        assert!(imtui.frame_text(false).starts_with("( ) One\n( ) Two\n(*) Three\n"));
+    }
+
+    #[test]
+    fn test_backend_shows_a_label_and_a_button() {
+        let backend = TestBackend::new(Point(20, 4));
+        let mut imtui = ImTui::init().build_with_backend(Box::new(backend.clone()));
+        frame(&mut imtui, None, |imtui| {
+            label(imtui, "Name");
+            button(imtui, "OK", Id(1));
+        });
+        assert_eq!(backend.row_string(0).trim_end(), "Name");
+        assert_eq!(backend.row_string(1).trim_end(), "[ OK ]");
+        assert_eq!(backend.cell(2, 1), 'O');
+        assert_eq!(backend.cell(30, 1), ' ');
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "Id(5) is registered twice")]
+    fn duplicate_id_panics() {
+        let mut imtui = ImTui::init().build_headless(Point(40, 5));
+        frame(&mut imtui, None, |imtui| {
+            button(imtui, "One", Id(5));
+            button(imtui, "Two", Id(5));
+        });
+    }
+
+    fn three_buttons(imtui: &mut ImTui) {
+        for i in 0..3 {
+            button(imtui, "B", Id(i));
+        }
+    }
+
+    #[test]
+    fn down_moves_the_focus() {
+        let mut imtui = ImTui::init().build_headless(Point(40, 5));
+        for key in [None, Some(KEY_DOWN), Some(KEY_DOWN)] {
+            frame(&mut imtui, key, three_buttons);
+        }
+        assert_eq!(imtui.focus(), 2);
+        assert_eq!(imtui.hot(), Some(Id(2)));
+    }
+
+    #[test]
+    fn tab_wraps_the_focus_around() {
+        let mut imtui = ImTui::init().build_headless(Point(40, 5));
+        frame(&mut imtui, None, three_buttons);
+        for _ in 0..3 {
+            frame(&mut imtui, Some('\t' as i32), three_buttons);
+        }
+        assert_eq!(imtui.focus(), 0);
+        assert!(imtui.focus_wrapped());
+
+        frame(&mut imtui, Some(KEY_BTAB), three_buttons);
+        assert_eq!(imtui.focus(), 2);
+    }
+
+    #[test]
+    fn focus_stops_at_the_ends_without_wrap() {
+        let mut imtui = ImTui::init().with_focus_wrap(false).build_headless(Point(40, 5));
+        frame(&mut imtui, None, three_buttons);
+        for _ in 0..3 {
+            frame(&mut imtui, Some('\t' as i32), three_buttons);
+        }
+        assert_eq!(imtui.focus(), 2);
+        assert!(!imtui.focus_wrapped());
+
+        frame(&mut imtui, Some(KEY_HOME), three_buttons);
+        frame(&mut imtui, Some(KEY_UP), three_buttons);
+        assert_eq!(imtui.focus(), 0);
+        frame(&mut imtui, Some(KEY_END), three_buttons);
+        assert_eq!(imtui.focus(), 2);
+    }
+
+    #[test]
+    fn password_field_shows_a_star_per_char() {
+        let backend = TestBackend::new(Point(30, 2));
+        let mut imtui = ImTui::init().build_with_backend(Box::new(backend.clone()));
+        let (mut buffer, mut cursor) = (String::new(), 0);
+        // Twice as wide as the field unmasked.
+        let text = "漢字".repeat(5) + "ab";
+        let keys: Vec<i32> = text.bytes().map(i32::from).collect();
+        feed_frames(&mut imtui, &keys, |imtui| {
+            password_field(imtui, &mut buffer, &mut cursor, Id(1));
+        });
+        assert_eq!(buffer, text);
+        assert_eq!(backend.row_string(0).trim_end(), "*".repeat(12));
+        assert_eq!(backend.cursor(), Some(Point(12, 0)));
+    }
+
+    #[test]
+    fn centered_label_goes_in_the_middle() {
+        let mut imtui = ImTui::init().build_headless(Point(40, 5));
+        frame(&mut imtui, None, |imtui| {
+            imtui.begin_layout_aligned(LayoutType::Vert, 0, Align::Center, 30);
+            label(imtui, "0123456789");
+            imtui.end_layout();
+        });
+        let row = imtui.frame_text(false).lines().next().unwrap().to_string();
+        assert_eq!(row.find('0'), Some(10));
+    }
+
+    #[test]
+    fn grid_columns_line_up() {
+        let mut imtui = ImTui::init().build_headless(Point(40, 5));
+        frame(&mut imtui, None, |imtui| {
+            imtui.begin_grid(2, 1);
+            button(imtui, "a", Id(1));
+            button(imtui, "b", Id(2));
+            button(imtui, "longer", Id(3));
+            button(imtui, "c", Id(4));
+            imtui.end_layout();
+        });
+        let x = |id: i32| imtui.rects.iter().find(|(x, _)| *x == Id(id)).unwrap().1.pos;
+        assert_eq!(x(1).0, x(3).0);
+        assert_eq!(x(2).0, x(4).0);
+        assert_eq!(x(2).0, x(3).0 + measure_button(&imtui.glyphs, "longer").0 + 1);
+        assert_eq!(x(1).1, x(2).1);
+        // A row and the pad below it.
+        assert_eq!(x(3).1, x(1).1 + 2);
+    }
+
+    #[test]
+    fn wrapped_label_reports_its_height() {
+        let mut imtui = ImTui::init().build_headless(Point(40, 5));
+        let text = "The quick brown fox jumps over the lazy sleepy dog";
+        assert_eq!(text.len(), 50);
+        let size = frame(&mut imtui, None, |imtui| {
+            wrapped_label(imtui, text, 20, false);
+            imtui.layouts.last().unwrap().size
+        });
+        assert_eq!(size.1, 3);
+        assert!(size.0 <= 20);
+        assert_eq!(imtui.frame_text(false).lines().nth(2), Some("sleepy dog"));
+    }
+
+    #[test]
+    fn status_bar_is_on_the_last_row() {
+        let mut imtui = ImTui::init().build_headless(Point(80, 24));
+        frame(&mut imtui, None, |imtui| {
+            label(imtui, "Above");
+            status_bar(imtui, "Ready");
+            label(imtui, "Below");
+        });
+        let screen = imtui.screen();
+        let row: String = screen[23].iter().map(|(x, _)| *x).collect();
+        assert_eq!(row, format!(" {:79}", "Ready"));
+        assert!(screen[23].iter().all(|(_, pair)| *pair == STATUS_PAIR));
+        // It takes no room in the layout.
+        assert!(imtui.frame_text(false).starts_with("Above\nBelow\n"));
+    }
+
+    #[test]
+    fn spacer_advances_the_layout() {
+        let mut imtui = ImTui::init().build_headless(Point(40, 10));
+        let (before, after) = frame(&mut imtui, None, |imtui| {
+            label(imtui, "Above");
+            let before = imtui.free_pos();
+            spacer(imtui, Point(0, 3));
+            (before, imtui.free_pos())
+        });
+        assert_eq!(after.1, before.1 + 3);
+        assert_eq!(imtui.frame_text(false).lines().filter(|x| !x.is_empty()).count(), 1);
+    }
+
+    #[test]
+    fn text_area_takes_enter_as_a_newline() {
+        let mut imtui = ImTui::init().build_headless(Point(40, 6));
+        let (mut buffer, mut cursor) = (String::new(), 0);
+        let keys = ['a' as i32, 'b' as i32, '\n' as i32, 'c' as i32, 'd' as i32, KEY_UP];
+        feed_frames(&mut imtui, &keys, |imtui| {
+            text_area(imtui, &mut buffer, &mut cursor, Point(10, 3), Id(1));
+        });
+        assert_eq!(buffer, "ab\ncd");
+        assert_eq!(cursor, 2);
+        assert!(imtui.is_active(Id(1)));
+        assert!(imtui.frame_text(false).starts_with("ab\ncd\n"));
+
+        let size = frame(&mut imtui, Some(27), |imtui| {
+            text_area(imtui, &mut buffer, &mut cursor, Point(10, 3), Id(1));
+            imtui.layouts.last().unwrap().size
+        });
+        assert!(!imtui.is_active(Id(1)));
+        assert_eq!(size, Point(10, 3));
+    }
+
+    #[test]
+    fn edit_field_reports_enter_once() {
+        let mut imtui = ImTui::init().build_headless(Point(40, 5));
+        let (mut buffer, mut cursor) = (String::new(), 0);
+        let mut submits = Vec::new();
+        let keys = ['h' as i32, 'i' as i32, '\n' as i32];
+        feed_frames(&mut imtui, &keys, |imtui| {
+            submits.push(edit_field_submitted(imtui, &mut buffer, &mut cursor, Id(1)));
+        });
+        frame(&mut imtui, None, |imtui| {
+            submits.push(edit_field_submitted(imtui, &mut buffer, &mut cursor, Id(1)));
+        });
+        assert_eq!(buffer, "hi");
+        assert_eq!(submits, [false, false, false, false, true, false]);
+
+        // Esc only stops the editing.
+        for key in ['\n' as i32, 27] {
+            let submitted = frame(&mut imtui, Some(key), |imtui| edit_field_submitted(imtui, &mut buffer, &mut cursor, Id(1)));
+            assert!(!submitted);
+        }
+        assert!(!imtui.is_active(Id(1)));
+    }
+
+    #[test]
+    fn widget_state_survives_frames() {
+        let mut imtui = ImTui::init().build_headless(Point(40, 5));
+        let mut buffer = String::new();
+        let keys = ['a' as i32, 'b' as i32, 'c' as i32, KEY_LEFT];
+        feed_frames(&mut imtui, &keys, |imtui| {
+            text_input(imtui, &mut buffer, Id(1));
+        });
+        for key in [None, None, Some('x' as i32)] {
+            frame(&mut imtui, key, |imtui| text_input(imtui, &mut buffer, Id(1)));
+        }
+        assert_eq!(buffer, "abxc");
+
+        frame(&mut imtui, None, |imtui| *imtui.state_mut::<usize>(Id(2)) += 5);
+        let count = frame(&mut imtui, None, |imtui| *imtui.state_mut::<usize>(Id(2)));
+        assert_eq!(count, 5);
+    }
+
+    #[test]
+    fn scrolled_pages_and_clips_to_the_viewport() {
+        let mut imtui = ImTui::init().build_headless(Point(20, 5));
+        let mut scroll = 0;
+        let mut content = |imtui: &mut ImTui| {
+            scrolled(imtui, &mut scroll, Id(1), |imtui| {
+                button(imtui, "Top", Id(2));
+                for i in 1..10 {
+                    label(imtui, &format!("row {}", i));
+                }
+                button(imtui, "Bottom", Id(3));
+            })
+        };
+        let first_row = |imtui: &ImTui| imtui.frame_text(false).lines().next().unwrap().to_string();
+
+        frame(&mut imtui, None, &mut content);
+        assert_eq!(first_row(&imtui), "[ Top ]");
+        assert!(imtui.prev_draws.iter().all(|draw| draw.pos.1 < 5));
+
+        frame(&mut imtui, Some(KEY_NPAGE), &mut content);
+        frame(&mut imtui, None, &mut content);
+        assert_eq!(first_row(&imtui), "row 5");
+
+        // Focusing a widget out of sight scrolls to it.
+        frame(&mut imtui, Some(KEY_END), &mut content);
+        frame(&mut imtui, None, &mut content);
+        assert_eq!(imtui.frame_text(false).lines().nth(4), Some("[ Bottom ]"));
+        frame(&mut imtui, Some(KEY_HOME), &mut content);
+        frame(&mut imtui, None, &mut content);
+        assert_eq!(first_row(&imtui), "[ Top ]");
+    }
+
+    #[test]
+    fn pushed_scopes_tell_the_same_label_apart() {
+        let mut imtui = ImTui::init().build_headless(Point(40, 5));
+        assert_eq!(imtui.id("Delete"), Id::from("Delete"));
+        imtui.push_id("first");
+        let first = imtui.id("Delete");
+        imtui.pop_id();
+        imtui.push_id("second");
+        let second = imtui.id("Delete");
+        imtui.pop_id();
+        assert_ne!(first, second);
+        assert_ne!(first, Id::from("Delete"));
+        imtui.push_id("first");
+        assert_eq!(imtui.id("Delete"), first);
+    }
+
+    #[test]
+    fn bracketed_paste_goes_in_at_the_cursor() {
+        let mut imtui = ImTui::init().build_headless(Point(40, 5));
+        let (mut buffer, mut cursor) = (String::new(), 0);
+        let keys = ['a' as i32, 'd' as i32, KEY_LEFT];
+        feed_frames(&mut imtui, &keys, |imtui| {
+            edit_field(imtui, &mut buffer, &mut cursor, Id(1));
+        });
+        imtui.feed_key(KEY_PASTE_BEGIN);
+        for byte in "bc".bytes() {
+            imtui.feed_key(byte.into());
+        }
+        imtui.feed_key(KEY_PASTE_END);
+        let changed = frame(&mut imtui, None, |imtui| edit_field(imtui, &mut buffer, &mut cursor, Id(1)));
+        assert!(changed);
+        assert_eq!(buffer, "abcd");
+        assert_eq!(cursor, 3);
+        assert_eq!(imtui.pasted(), "bc");
+    }
+
+    #[test]
+    fn scrollbar_thumb_is_proportional() {
+        assert_eq!(scrollbar_thumb(10, 5, 20, 10), 2..7);
+        assert_eq!(scrollbar_thumb(10, 0, 20, 10), 0..5);
+        assert_eq!(scrollbar_thumb(10, 10, 20, 10), 5..10);
+        assert_eq!(scrollbar_thumb(10, 0, 5, 10), 0..0);
+        assert_eq!(scrollbar_thumb(10, 0, 0, 0), 0..0);
+
+        let mut imtui = ImTui::init().build_headless(Point(40, 12));
+        let size = frame(&mut imtui, None, |imtui| {
+            scrollbar_column(imtui, 5, 20, 10, 10);
+            imtui.layouts.last().unwrap().size
+        });
+        assert_eq!(size, Point(1, 10));
+    }
+
+    #[test]
+    fn mouse_move_makes_the_button_under_it_hot() {
+        let mut imtui = ImTui::init().with_hover_focus(true).build_headless(Point(40, 5));
+        frame(&mut imtui, None, three_buttons);
+        frame(&mut imtui, None, three_buttons);
+        assert_eq!(imtui.hot(), Some(Id(0)));
+
+        imtui.feed_mouse(Point(2, 2), MouseKind::Move);
+        frame(&mut imtui, None, three_buttons);
+        assert_eq!(imtui.hot(), Some(Id(2)));
+
+        // A key after the move wins until the mouse moves again.
+        frame(&mut imtui, Some('w' as i32), three_buttons);
+        assert_eq!(imtui.hot(), Some(Id(1)));
+        frame(&mut imtui, None, three_buttons);
+        assert_eq!(imtui.hot(), Some(Id(1)));
+    }
+
+    #[test]
+    fn focus_stays_valid_when_the_ids_shrink() {
+        let mut imtui = ImTui::init().build_headless(Point(40, 5));
+        let buttons = |n: i32| move |imtui: &mut ImTui| {
+            for i in 0..n {
+                button(imtui, "B", Id(i));
+            }
+            imtui.hot()
+        };
+        frame(&mut imtui, None, buttons(3));
+        frame(&mut imtui, Some('s' as i32), buttons(3));
+        assert_eq!(imtui.hot(), Some(Id(1)));
+
+        // Losing a widget after the focused one moves nothing.
+        for _ in 0..2 {
+            assert_eq!(frame(&mut imtui, None, buttons(2)), Some(Id(1)));
+            assert_eq!(imtui.focus(), 1);
+        }
+
+        // Losing the focused one moves the focus to the one before, and
+        // the keys move on from there.
+        frame(&mut imtui, None, buttons(1));
+        assert_eq!(frame(&mut imtui, None, buttons(1)), Some(Id(0)));
+        assert_eq!(imtui.focus(), 0);
+        assert_eq!(frame(&mut imtui, Some('s' as i32), buttons(1)), Some(Id(0)));
+        assert_eq!(imtui.focus(), 0);
+    }
+
+    #[test]
+    fn combo_box_survives_its_options_shrinking() {
+        let mut imtui = ImTui::init().build_headless(Point(40, 12));
+        let mut selected = 0;
+        let all = ["a", "b", "c", "d"];
+        frame(&mut imtui, None, |imtui| combo_box(imtui, &all, &mut selected, Id(1)));
+        for key in ['\n' as i32, KEY_DOWN, KEY_DOWN, KEY_DOWN] {
+            frame(&mut imtui, Some(key), |imtui| combo_box(imtui, &all, &mut selected, Id(1)));
+        }
+        assert!(imtui.is_active(Id(1)));
+
+        frame(&mut imtui, None, |imtui| combo_box(imtui, &all[..2], &mut selected, Id(1)));
+        frame(&mut imtui, Some('\n' as i32), |imtui| combo_box(imtui, &all[..2], &mut selected, Id(1)));
+        assert_eq!(selected, 1);
+
+        frame(&mut imtui, Some('\n' as i32), |imtui| combo_box(imtui, &all[..2], &mut selected, Id(1)));
+        assert!(imtui.is_active(Id(1)));
+        frame(&mut imtui, None, |imtui| combo_box(imtui, &[], &mut selected, Id(1)));
+        assert!(!imtui.is_active(Id(1)));
+        frame(&mut imtui, Some('\n' as i32), |imtui| combo_box(imtui, &[], &mut selected, Id(1)));
+        assert!(!imtui.is_active(Id(1)));
+    }
+
+
+    // A scope and the call that leaves it unbalanced.
+    type ScopeCase = (Scope, fn(&mut ImTui));
+
+    #[test]
+    fn extra_ends_are_reported_without_panicking() {
+        let mut imtui = ImTui::init().build_headless(Point(40, 5));
+        let extra_ends: [ScopeCase; 8] = [
+            (Scope::Layout, |imtui| imtui.end_layout()),
+            (Scope::Clip, |imtui| imtui.pop_clip()),
+            (Scope::Layer, |imtui| imtui.pop_layer()),
+            (Scope::Id, |imtui| imtui.pop_id()),
+            (Scope::Disabled, end_disabled),
+            (Scope::Frame, end_frame),
+            (Scope::Split, next_split),
+            (Scope::Split, end_split),
+        ];
+        for (scope, extra_end) in extra_ends {
+            imtui.begin(Point(0, 0));
+            button(&mut imtui, "b1", Id(1));
+            extra_end(&mut imtui);
+            assert_eq!(imtui.end(), Err(ImTuiError::Unbalanced(scope)));
+            imtui.present();
+        }
+    }
+
+    #[test]
+    fn missing_ends_are_reported_and_dropped() {
+        let mut imtui = ImTui::init().build_headless(Point(40, 5));
+        let missing_ends: [ScopeCase; 7] = [
+            (Scope::Layout, |imtui| imtui.begin_layout(LayoutType::Horz, 1)),
+            (Scope::Clip, |imtui| imtui.push_clip(Rect::new(Point(0, 0), Point(1, 1)))),
+            (Scope::Layer, |imtui| imtui.push_layer(1)),
+            (Scope::Id, |imtui| imtui.push_id("row")),
+            (Scope::Disabled, |imtui| begin_disabled(imtui, true)),
+            (Scope::Frame, |imtui| begin_frame(imtui, FrameStyle::default())),
+            (Scope::Split, |imtui| begin_split(imtui, &mut 0.5, LayoutType::Horz, Id(9))),
+        ];
+        for (scope, begin) in missing_ends {
+            imtui.begin(Point(0, 0));
+            begin(&mut imtui);
+            assert_eq!(imtui.end(), Err(ImTuiError::Unbalanced(scope)));
+            imtui.present();
+
+            let (disabled, clip, layer, id) = frame(&mut imtui, None, |imtui| {
+                button(imtui, "b1", Id(1));
+                (imtui.is_disabled(), imtui.clip_rect(), imtui.layer(), imtui.id("b1"))
+            });
+            assert!(!disabled);
+            assert_eq!(clip, Rect::new(Point(0, 0), Point(40, 5)));
+            assert_eq!(layer, 0);
+            assert_eq!(id, Id::from("b1"));
+            assert_eq!(imtui.ids(), &[Id(1)]);
+        }
+    }
+
+    // Five buttons in a layout capped to three rows, and its width.
+    fn capped_buttons(imtui: &mut ImTui) -> i32 {
+        imtui.begin_layout_max(LayoutType::Vert, 0, Point(30, 3));
+        for i in 0..5 {
+            button(imtui, "b", Id(i));
+        }
+        imtui.end_layout();
+        imtui.layouts.last().unwrap().size.0
+    }
+
+    #[test]
+    fn capped_layout_hides_the_widgets_under_its_marker() {
+        let mut imtui = ImTui::init().build_headless(Point(40, 10));
+        let width = frame(&mut imtui, None, capped_buttons);
+        // The marker takes the place of the third one.
+        assert_eq!(imtui.ids(), &[Id(0), Id(1)]);
+        assert!(imtui.rects.iter().all(|(id, _)| *id == Id(0) || *id == Id(1)));
+        assert_eq!(imtui.frame_text(false).lines().nth(2), Some("▼ (+3 more)"));
+        assert_eq!(width, text_width("▼ (+3 more)"));
+
+        // Tab goes around the shown ones only.
+        for _ in 0..2 {
+            frame(&mut imtui, Some('\t' as i32), capped_buttons);
+        }
+        assert_eq!(imtui.hot(), Some(Id(0)));
+    }
+
+
+    #[test]
+    fn widths_are_in_columns() {
+        assert_eq!(measure_label("漢字"), Point(4, 1));
+        assert_eq!(measure_label("café"), Point(4, 1));
+
+        let mut imtui = ImTui::init().build_headless(Point(40, 5));
+        let after = frame(&mut imtui, None, |imtui| {
+            imtui.begin_layout(LayoutType::Horz, 0);
+            label(imtui, "漢字");
+            label(imtui, "café");
+            let after = imtui.free_pos();
+            imtui.end_layout();
+            after
+        });
+        assert_eq!(after, Point(8, 0));
+
+        for truncation in [Truncation::End, Truncation::Middle] {
+            let cut = truncate("漢字漢字漢字", 5, truncation);
+            assert!(text_width(&cut) <= 5, "{:?} is too wide", cut);
+        }
+        assert_eq!(truncate("漢字漢字漢字", 5, Truncation::End), "漢字…");
+        assert_eq!(truncate("café au lait", 6, Truncation::End), "café …");
+
+        for (line, _) in wrap_words("漢字漢字漢字 café", 5) {
+            assert!(line.iter().all(|word| text_width(word) <= 5), "{:?} is too wide", line);
+        }
+        assert_eq!(wrap_words("漢字漢字漢字", 5)[0].0, vec!["漢字"]);
+    }
+
+    #[test]
+    fn highlight_goes_by_columns() {
+        let mut imtui = ImTui::init().build_headless(Point(20, 2));
+        frame(&mut imtui, None, |imtui| highlight_label(imtui, "漢字café", "CAFÉ", HOT_PAIR));
+        let screen = imtui.screen();
+        let row: String = screen[0].iter().map(|(x, _)| *x).filter(|x| *x != WIDE_TAIL).collect();
+        assert_eq!(row.trim_end(), "漢字café");
+        assert_eq!(screen[0][3].1, INACTIVE_PAIR);
+        assert!(screen[0][4..8].iter().all(|(_, pair)| *pair == HOT_PAIR));
+        assert_eq!(screen[0][8].1, INACTIVE_PAIR);
+    }
+
+
+    // A field with a Save button bound to 's' after it, if `shown`.
+    fn save_form(imtui: &mut ImTui, buffer: &mut String, cursor: &mut usize, shown: bool) -> bool {
+        edit_field(imtui, buffer, cursor, Id(1));
+        shown && button_with_key(imtui, "Save", Key::Char('s'), Id(2))
+    }
+
+    #[test]
+    fn key_button_fires_while_drawn_and_nothing_is_active() {
+        let mut imtui = ImTui::init().build_headless(Point(40, 5));
+        let (mut buffer, mut cursor) = (String::new(), 0);
+        let mut step = |imtui: &mut ImTui, key: Option<i32>, shown: bool| {
+            frame(imtui, key, |imtui| save_form(imtui, &mut buffer, &mut cursor, shown))
+        };
+        assert!(!step(&mut imtui, None, true));
+        // The focus is on the field.
+        assert!(step(&mut imtui, Some('s' as i32), true));
+
+        // The active field gets the key instead.
+        assert!(!step(&mut imtui, Some('\n' as i32), true));
+        assert!(!step(&mut imtui, Some('s' as i32), true));
+        assert!(!step(&mut imtui, Some(27), true));
+
+        // Not drawn for a frame, the button loses its binding.
+        step(&mut imtui, None, false);
+        assert!(imtui.bindings.is_empty());
+        assert!(!step(&mut imtui, Some('s' as i32), true));
+        assert_eq!(buffer, "s");
+    }
+
+    #[test]
+    fn key_button_keeps_a_bind_of_its_key() {
+        let mut imtui = ImTui::init().build_headless(Point(40, 5));
+        imtui.bind(Key::Char('q'), Id(1), "Quit");
+        let step = |imtui: &mut ImTui, key: Option<i32>, shown: bool| {
+            frame(imtui, key, |imtui| {
+                if shown {
+                    button_with_key(imtui, "Quit", Key::Char('q'), Id(1));
+                }
+                imtui.fired(Id(1))
+            })
+        };
+        step(&mut imtui, None, true);
+        step(&mut imtui, None, false);
+        assert!(step(&mut imtui, Some('q' as i32), false));
+        step(&mut imtui, None, true);
+        step(&mut imtui, None, false);
+        assert!(step(&mut imtui, Some('q' as i32), false));
+        assert_eq!(imtui.bindings().count(), 1);
+    }
+
+    #[test]
+    fn damage_is_what_changed() {
+        let mut imtui = ImTui::init().build_headless(Point(20, 3));
+        frame(&mut imtui, None, |imtui| label(imtui, "abc"));
+        assert_eq!(imtui.damage(), &[Rect::new(Point(0, 0), Point(3, 1))]);
+
+        frame(&mut imtui, None, |imtui| label(imtui, "abc"));
+        assert_eq!(imtui.damage(), &[]);
+
+        frame(&mut imtui, None, |imtui| label(imtui, "a"));
+        assert_eq!(imtui.damage(), &[Rect::new(Point(1, 0), Point(2, 1))]);
+    }
+
+
+    #[test]
+    fn button_is_drawn_in_its_own_pair_until_focused() {
+        let backend = TestBackend::new(Point(20, 2));
+        let mut imtui = ImTui::init().build_with_backend(Box::new(backend.clone()));
+        let buttons = |imtui: &mut ImTui| {
+            imtui.begin_layout(LayoutType::Horz, 1);
+            button(imtui, "a", Id(1));
+            button_with_pair(imtui, "ok", SWATCH_PAIR + COLOR_GREEN, Id(2));
+            imtui.end_layout();
+        };
+        // The focus goes to the first one from the second frame on.
+        for _ in 0..2 {
+            frame(&mut imtui, None, buttons);
+        }
+        let x = measure_button(&imtui.glyphs(), "a").0 + 1;
+        let width = measure_button(&imtui.glyphs(), "ok").0;
+        assert_eq!(backend.pair(0, 0), HOT_PAIR);
+        assert!((x..x + width).all(|x| backend.pair(x, 0) == SWATCH_PAIR + COLOR_GREEN));
+
+        frame(&mut imtui, Some('\t' as i32), buttons);
+        assert!((x..x + width).all(|x| backend.pair(x, 0) == HOT_PAIR));
+        assert_eq!(backend.pair(0, 0), INACTIVE_PAIR);
+    }
+
+
+    #[test]
+    fn disabled_button_is_never_clicked() {
+        let backend = TestBackend::new(Point(20, 2));
+        let mut imtui = ImTui::init().build_with_backend(Box::new(backend.clone()));
+        let mut state = false;
+        let widgets = |imtui: &mut ImTui, state: &mut bool| {
+            imtui.begin_layout(LayoutType::Vert, 0);
+            let clicked = button_enabled(imtui, "a", false, Id(1));
+            let toggled = checkbox_enabled(imtui, "b", state, false, Id(2));
+            imtui.end_layout();
+            clicked || toggled
+        };
+        // The button would be focused from the second frame on, and Enter
+        // would click it the frame after it.
+        for key in [None, None, Some('\n' as i32), None, Some(' ' as i32), None] {
+            assert!(!frame(&mut imtui, key, |imtui| widgets(imtui, &mut state)));
+            assert!(imtui.ids().is_empty());
+            assert_eq!(imtui.focused(), None);
+        }
+        assert!(!state);
+        assert_eq!(backend.pair(0, 0), DISABLED_PAIR);
+        assert_eq!(backend.pair(0, 1), DISABLED_PAIR);
+    }
+
+
+    #[test]
+    fn int_field_takes_only_digits() {
+        let mut imtui = ImTui::init().build_headless(Point(40, 5));
+        let mut value = 0;
+        let mut result = Ok(());
+        let keys: Vec<i32> = "42x".bytes().map(i32::from).collect();
+        feed_frames(&mut imtui, &keys, |imtui| {
+            result = int_field(imtui, &mut value, i64::MIN..=i64::MAX, Id(1));
+        });
+        assert_eq!(value, 42);
+        assert_eq!(result, Ok(()));
+
+        // Past the 18th digit the number does not fit.
+        let mut imtui = ImTui::init().build_headless(Point(40, 5));
+        let mut value = 0;
+        let keys: Vec<i32> = "9".repeat(20).bytes().map(i32::from).collect();
+        feed_frames(&mut imtui, &keys, |imtui| {
+            result = int_field(imtui, &mut value, i64::MIN..=i64::MAX, Id(1));
+        });
+        assert_eq!(value, 999_999_999_999_999_999);
+        assert_eq!(result, Err(ParseErr::OutOfRange));
+    }
+
+
+    #[test]
+    fn ansi_backend_writes_the_frame_with_escapes() {
+        #[derive(Clone, Default)]
+        struct Shared(Rc<RefCell<Vec<u8>>>);
+        impl Write for Shared {
+            fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+                self.0.borrow_mut().extend_from_slice(buf);
+                Ok(buf.len())
+            }
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let shared = Shared::default();
+        let backend = AnsiBackend::new(shared.clone(), Point(10, 2));
+        let mut imtui = ImTui::init().build_with_backend(Box::new(backend));
+        imtui.set_theme(Theme::monokai());
+        frame(&mut imtui, None, |imtui| {
+            imtui.put_str(Point(2, 1), "hi", ERROR_PAIR);
+        });
+        imtui.present();
+
+        let out = String::from_utf8(shared.0.borrow().clone()).unwrap();
+        assert!(out.contains("\x1b[2;3H"));
+        assert!(out.contains("\x1b[0;38;5;197;48;5;235mhi"));
+        assert!(out.ends_with("\x1b[?25l"));
+    }
+
+
+    #[test]
+    fn rejected_input_gives_feedback() {
+        let backend = TestBackend::new(Point(40, 5));
+        let mut imtui = ImTui::init().build_with_backend(Box::new(backend.clone()));
+        let (mut buffer, mut cursor) = (String::new(), 0);
+        let keys = ['a' as i32, 'd' as i32, KEY_LEFT];
+        feed_frames(&mut imtui, &keys, |imtui| {
+            edit_field_max_len(imtui, &mut buffer, &mut cursor, 3, Id(1));
+        });
+        imtui.feed_key(KEY_PASTE_BEGIN);
+        for byte in "bcx".bytes() {
+            imtui.feed_key(byte.into());
+        }
+        imtui.feed_key(KEY_PASTE_END);
+        frame(&mut imtui, None, |imtui| edit_field_max_len(imtui, &mut buffer, &mut cursor, 3, Id(1)));
+        assert_eq!((buffer.as_str(), cursor), ("abd", 2));
+        assert_eq!(backend.feedbacks(), 1);
+        frame(&mut imtui, Some('z' as i32), |imtui| edit_field_max_len(imtui, &mut buffer, &mut cursor, 3, Id(1)));
+        assert_eq!(buffer, "abd");
+        assert_eq!(backend.feedbacks(), 2);
+
+        let backend = TestBackend::new(Point(40, 5));
+        let mut imtui = ImTui::init().build_with_backend(Box::new(backend.clone()));
+        let disabled = |imtui: &mut ImTui| {
+            begin_disabled(imtui, true);
+            let clicked = button_with_key(imtui, "Ok", Key::Char('o'), Id(1));
+            end_disabled(imtui);
+            clicked
+        };
+        assert!(!frame(&mut imtui, None, disabled));
+        assert!(!frame(&mut imtui, Some('o' as i32), disabled));
+        assert_eq!(backend.feedbacks(), 1);
+
+        let backend = TestBackend::new(Point(40, 5));
+        let mut imtui = ImTui::init().with_focus_wrap(false).build_with_backend(Box::new(backend.clone()));
+        frame(&mut imtui, None, three_buttons);
+        for key in [KEY_UP, '\t' as i32, KEY_DOWN, KEY_DOWN] {
+            frame(&mut imtui, Some(key), three_buttons);
+        }
+        assert_eq!(imtui.focus(), 2);
+        assert_eq!(backend.feedbacks(), 2);
+    }
+
+
+    #[test]
+    fn slider_takes_a_reversed_range() {
+        let mut imtui = ImTui::init().build_headless(Point(40, 5));
+        let mut value = 9;
+        // As a range from a max and a min worked out at runtime would be.
+        let range = || RangeInclusive::new(10, 0);
+        for key in [None, None, Some(KEY_RIGHT), Some(KEY_RIGHT)] {
+            frame(&mut imtui, key, |imtui| slider(imtui, &mut value, range(), 1, Id(1)));
+        }
+        assert_eq!(value, 10);
+        frame(&mut imtui, Some(KEY_LEFT), |imtui| slider(imtui, &mut value, range(), 1, Id(1)));
+        assert_eq!(value, 9);
+    }
+}
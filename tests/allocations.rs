@@ -0,0 +1,75 @@
+// Counts the heap allocations of the frames of an app once the buffers of
+// ImTui have grown, which should be none, so a UI redrawn at a high frame
+// rate does not keep the allocator busy. Only the allocations of the
+// thread running the frames are counted.
+
+use std::alloc::{self, GlobalAlloc, System};
+use std::cell::Cell;
+use tuple::*;
+
+struct CountingAlloc;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: alloc::Layout) -> *mut u8 {
+        ALLOCATIONS.with(|x| x.set(x.get() + 1));
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: alloc::Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: alloc::Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.with(|x| x.set(x.get() + 1));
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+fn allocations() -> usize {
+    ALLOCATIONS.with(|x| x.get())
+}
+
+// A frame of the usual widgets, some of them changing every other frame
+// so present() has something to draw.
+fn frame(imtui: &mut ImTui, n: usize, state: &mut bool) {
+    imtui.begin(Point(0, 0));
+    begin_window(imtui, "Settings");
+    label(imtui, ["tick", "tock"][n % 2]);
+    highlight_label(imtui, "Find the needle in the haystack", "the", HOT_PAIR);
+    checkbox(imtui, "Enabled", state, Id::from("Enabled"));
+    imtui.begin_layout(LayoutType::Horz, 1);
+    button(imtui, "OK", Id::from("OK"));
+    button(imtui, "Cancel", Id::from("Cancel"));
+    imtui.end_layout();
+    end_window(imtui);
+    imtui.end().unwrap();
+    imtui.present();
+}
+
+#[test]
+fn frames_do_not_allocate() {
+    let backend = TestBackend::new(Point(80, 24));
+    let mut imtui = ImTui::init().build_with_backend(Box::new(backend.clone()));
+    let mut state = false;
+    // The buffers grow to the size of the frame first.
+    for n in 0..10 {
+        frame(&mut imtui, n, &mut state);
+    }
+
+    let frames = 100;
+    let before = allocations();
+    for n in 0..frames {
+        frame(&mut imtui, n, &mut state);
+    }
+    let per_frame = (allocations() - before) as f64 / frames as f64;
+    assert_eq!(per_frame, 0.0, "{} allocations per frame", per_frame);
+    // The last frame was drawn.
+    assert!(backend.row_string(1).starts_with("│tock"));
+}
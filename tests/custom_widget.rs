@@ -0,0 +1,62 @@
+// A widget written outside of the crate, with the primitives ImTui makes
+// public only, takes part in the focus, the hot/active state and the
+// layout like the built-in ones.
+
+use tuple::*;
+
+// A counter going up every time it is clicked, with the caret shown after
+// the number while it is active.
+fn counter(imtui: &mut ImTui, count: &mut i32, id: Id) -> bool {
+    let mut clicked = false;
+    let mut pair = INACTIVE_PAIR;
+    if imtui.is_active(id) {
+        imtui.deactivate();
+        *count += 1;
+        clicked = true;
+    } else if imtui.is_hot(id) {
+        pair = HOT_PAIR;
+        if !imtui.any_active() && imtui.key() == Some(Key::Enter) {
+            imtui.activate(id);
+        }
+    }
+
+    imtui.register_id(id);
+    let pos = imtui.free_pos();
+    let text = format!("<{}>", count);
+    imtui.put_str(pos, &text, pair);
+    if imtui.is_active(id) {
+        imtui.set_caret(pos + Point(text_width(&text), 0));
+    }
+    imtui.add_size(measure_label(&text));
+    clicked
+}
+
+fn frame(imtui: &mut ImTui, key: Option<i32>, counts: &mut [i32; 2]) {
+    if let Some(key) = key {
+        imtui.feed_key(key);
+    }
+    imtui.begin(Point(0, 0));
+    imtui.begin_layout(LayoutType::Horz, 1);
+    counter(imtui, &mut counts[0], Id::from("first"));
+    counter(imtui, &mut counts[1], Id::from("second"));
+    imtui.end_layout();
+    imtui.end().unwrap();
+    imtui.present();
+}
+
+#[test]
+fn custom_widget_is_focused_and_clicked() {
+    let backend = TestBackend::new(Point(20, 2));
+    let mut imtui = ImTui::init().build_with_backend(Box::new(backend.clone()));
+    let mut counts = [0, 0];
+    frame(&mut imtui, None, &mut counts);
+    assert_eq!(imtui.ids(), &[Id::from("first"), Id::from("second")]);
+
+    frame(&mut imtui, Some('\t' as i32), &mut counts);
+    assert_eq!(imtui.hot(), Some(Id::from("second")));
+    frame(&mut imtui, Some('\n' as i32), &mut counts);
+    assert_eq!(backend.cursor(), Some(Point(7, 0)));
+    frame(&mut imtui, None, &mut counts);
+    assert_eq!(counts, [0, 1]);
+    assert_eq!(backend.row_string(0).trim_end(), "<0> <1>");
+}